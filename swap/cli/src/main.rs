@@ -0,0 +1,361 @@
+//! swap程序的命令行客户端
+//!
+//! 通过可配置的RPC节点（包括本地验证节点）创建、成交、撤销报价，
+//! 并列出链上尚未关闭的报价，供场外结算脚本调用
+
+mod offers;
+mod rpc;
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::{
+    prelude::{Clock, Pubkey},
+    solana_program::{instruction::Instruction, sysvar},
+    AccountDeserialize, Discriminator,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use swap::{MakerProfile, Offer, SwapConfig};
+
+use offers::{
+    cancel_offer_instruction, check_take_supported, config_address, make_offer_instruction,
+    maker_profile_address, offer_address, quote_take, take_offer_instruction, vault_address,
+    MakeOfferArgs, Pair, TakeOfferArgs,
+};
+use rpc::{Memcmp, RpcClient};
+
+/// 报价账户中maker字段的偏移（8字节鉴别器 + 8字节报价ID）
+const OFFER_MAKER_OFFSET: usize = 16;
+/// 报价账户中token_mint_a字段的偏移
+const OFFER_TOKEN_MINT_A_OFFSET: usize = OFFER_MAKER_OFFSET + 32;
+/// 报价账户中token_mint_b字段的偏移
+const OFFER_TOKEN_MINT_B_OFFSET: usize = OFFER_TOKEN_MINT_A_OFFSET + 32;
+
+#[derive(Parser)]
+#[command(name = "swap-cli", version, about = "swap程序的命令行客户端")]
+struct Cli {
+    /// RPC节点地址，默认连接本地验证节点
+    #[arg(long, env = "SWAP_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// 签名并支付手续费的钱包文件，默认为~/.config/solana/id.json
+    #[arg(long, env = "SWAP_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// 读取账户与确认交易使用的承诺级别
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 以固定价格出售代币A换取代币B，报价ID由创建者档案分配
+    Make {
+        /// 出售的代币A的Mint地址
+        #[arg(long)]
+        token_mint_a: Pubkey,
+        /// 想要的代币B的Mint地址
+        #[arg(long)]
+        token_mint_b: Pubkey,
+        /// 出售的代币A数量（最小单位）
+        #[arg(long)]
+        token_a_offered_amount: u64,
+        /// 想要的代币B数量（最小单位）
+        #[arg(long)]
+        token_b_wanted_amount: u64,
+        /// 报价过期的Unix时间戳
+        #[arg(long)]
+        expires_at: Option<i64>,
+        /// 指定唯一的接受者
+        #[arg(long)]
+        allowed_taker: Option<Pubkey>,
+    },
+    /// 按代币B数量成交报价，可部分成交
+    Take {
+        /// 报价创建者
+        #[arg(long)]
+        maker: Pubkey,
+        /// 报价ID
+        #[arg(long)]
+        offer_id: u64,
+        /// 本次支付的代币B数量，默认成交全部剩余部分，超过剩余数量时按剩余数量成交
+        #[arg(long)]
+        token_b_fill_amount: Option<u64>,
+        /// 至少应收到的代币A数量，默认为按链上时间对报价计算的数量
+        #[arg(long)]
+        min_token_a_amount: Option<u64>,
+        /// 至多支付的代币B数量（含手续费），默认为按链上时间对报价计算的数量
+        #[arg(long)]
+        max_token_b_amount: Option<u64>,
+    },
+    /// 撤销钱包创建的报价并取回托管的代币A
+    Cancel {
+        /// 报价ID
+        #[arg(long)]
+        offer_id: u64,
+    },
+    /// 以每行一个JSON对象的形式列出报价
+    List {
+        /// 只列出该创建者的报价
+        #[arg(long)]
+        maker: Option<Pubkey>,
+        /// 只列出出售该代币的报价
+        #[arg(long)]
+        token_mint_a: Option<Pubkey>,
+        /// 只列出想要该代币的报价
+        #[arg(long)]
+        token_mint_b: Option<Pubkey>,
+        /// 同时列出已撤销、已成交和已过期的报价
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new(cli.url, cli.commitment);
+
+    match cli.command {
+        Command::Make {
+            token_mint_a,
+            token_mint_b,
+            token_a_offered_amount,
+            token_b_wanted_amount,
+            expires_at,
+            allowed_taker,
+        } => {
+            let maker = load_keypair(cli.keypair.as_deref())?;
+            let pair = fetch_pair(&rpc, &token_mint_a, &token_mint_b)?;
+            let offer_id = next_offer_id(&rpc, &maker.pubkey())?;
+            let instruction = make_offer_instruction(
+                &maker.pubkey(),
+                offer_id,
+                &pair,
+                &MakeOfferArgs {
+                    token_a_offered_amount,
+                    token_b_wanted_amount,
+                    expires_at,
+                    allowed_taker,
+                },
+            );
+            let signature = send(&rpc, &maker, instruction)?;
+            println!(
+                "{}",
+                json!({
+                    "signature": signature,
+                    "offer_id": offer_id,
+                    "offer": offer_address(&maker.pubkey(), offer_id).to_string(),
+                })
+            );
+        }
+        Command::Take {
+            maker,
+            offer_id,
+            token_b_fill_amount,
+            min_token_a_amount,
+            max_token_b_amount,
+        } => {
+            let taker = load_keypair(cli.keypair.as_deref())?;
+            let offer = fetch_offer(&rpc, &offer_address(&maker, offer_id))?;
+            let config = fetch_config(&rpc)?;
+            let pair = fetch_pair(&rpc, &offer.token_mint_a, &offer.token_mint_b)?;
+            let mint_a = rpc
+                .get_account(&pair.token_mint_a)?
+                .ok_or_else(|| anyhow!("mint {} not found", pair.token_mint_a))?;
+            let mint_b = rpc
+                .get_account(&pair.token_mint_b)?
+                .ok_or_else(|| anyhow!("mint {} not found", pair.token_mint_b))?;
+            check_take_supported(&offer, &pair, &mint_a.data, &mint_b.data)?;
+            let quote = quote_take(
+                &offer,
+                &config,
+                &pair,
+                &mint_a.data,
+                &mint_b.data,
+                token_b_fill_amount,
+                &fetch_clock(&rpc)?,
+            )?;
+            let instruction = take_offer_instruction(
+                &taker.pubkey(),
+                &offer,
+                &config,
+                &pair,
+                &TakeOfferArgs {
+                    token_b_fill_amount: quote.token_b_fill_amount,
+                    expected_token_a_amount: min_token_a_amount.unwrap_or(quote.token_a_amount),
+                    max_token_b_amount: max_token_b_amount.unwrap_or(quote.token_b_amount),
+                },
+            );
+            let signature = send(&rpc, &taker, instruction)?;
+            println!(
+                "{}",
+                json!({
+                    "signature": signature,
+                    "token_b_fill_amount": quote.token_b_fill_amount,
+                })
+            );
+        }
+        Command::Cancel { offer_id } => {
+            let maker = load_keypair(cli.keypair.as_deref())?;
+            let offer_address = offer_address(&maker.pubkey(), offer_id);
+            let offer = fetch_offer(&rpc, &offer_address)?;
+            if offer.is_basket() {
+                bail!("basket offers are not supported by swap-cli");
+            }
+            let token_program_a = fetch_token_program(&rpc, &offer.token_mint_a)?;
+            // 托管账户可能已在之前的操作中关闭，此时不传托管账户
+            let with_vault = rpc
+                .get_account(&vault_address(
+                    &offer_address,
+                    &offer.token_mint_a,
+                    &token_program_a,
+                ))?
+                .is_some();
+            let instruction = cancel_offer_instruction(&offer, &token_program_a, with_vault);
+            let signature = send(&rpc, &maker, instruction)?;
+            println!("{}", json!({ "signature": signature }));
+        }
+        Command::List {
+            maker,
+            token_mint_a,
+            token_mint_b,
+            all,
+        } => {
+            let mut filters = vec![Memcmp {
+                offset: 0,
+                bytes: Offer::DISCRIMINATOR.to_vec(),
+            }];
+            for (offset, address) in [
+                (OFFER_MAKER_OFFSET, maker),
+                (OFFER_TOKEN_MINT_A_OFFSET, token_mint_a),
+                (OFFER_TOKEN_MINT_B_OFFSET, token_mint_b),
+            ] {
+                if let Some(address) = address {
+                    filters.push(Memcmp {
+                        offset,
+                        bytes: address.to_bytes().to_vec(),
+                    });
+                }
+            }
+
+            let now = fetch_clock(&rpc)?.unix_timestamp;
+            for (address, account) in rpc.get_program_accounts(&swap::ID, &filters)? {
+                let offer = Offer::try_deserialize(&mut account.data.as_slice())
+                    .with_context(|| format!("failed to decode offer {address}"))?;
+                let is_open = !offer.is_cancelled && !offer.is_filled() && !offer.is_expired(now);
+                if !all && !is_open {
+                    continue;
+                }
+                println!("{}", offer_json(&address, &offer, now));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 读取钱包文件，未指定时使用Solana CLI的默认钱包
+fn load_keypair(path: Option<&Path>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => PathBuf::from(std::env::var("HOME").context("HOME is not set")?)
+            .join(".config/solana/id.json"),
+    };
+    read_keypair_file(&path)
+        .map_err(|error| anyhow!("failed to read keypair {}: {error}", path.display()))
+}
+
+/// 以钱包为手续费支付者签名并发送单条指令的交易
+fn send(rpc: &RpcClient, payer: &Keypair, instruction: Instruction) -> Result<String> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        rpc.get_latest_blockhash()?,
+    );
+    rpc.send_and_confirm(&transaction)
+}
+
+/// 读取并解码程序拥有的Anchor账户
+fn fetch_anchor_account<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T> {
+    let account = rpc
+        .get_account(address)?
+        .ok_or_else(|| anyhow!("account {address} not found"))?;
+    if account.owner != swap::ID {
+        bail!("account {address} is not owned by the swap program");
+    }
+    T::try_deserialize(&mut account.data.as_slice())
+        .with_context(|| format!("failed to decode account {address}"))
+}
+
+fn fetch_offer(rpc: &RpcClient, address: &Pubkey) -> Result<Offer> {
+    fetch_anchor_account(rpc, address)
+}
+
+fn fetch_config(rpc: &RpcClient) -> Result<SwapConfig> {
+    fetch_anchor_account(rpc, &config_address())
+}
+
+/// 创建者档案中的下一个报价ID，档案尚未创建时为0
+fn next_offer_id(rpc: &RpcClient, maker: &Pubkey) -> Result<u64> {
+    let address = maker_profile_address(maker);
+    if rpc.get_account(&address)?.is_none() {
+        return Ok(0);
+    }
+    let profile: MakerProfile = fetch_anchor_account(rpc, &address)?;
+    Ok(profile.next_offer_id)
+}
+
+/// 代币Mint账户的所有者即其代币程序
+fn fetch_token_program(rpc: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    Ok(rpc
+        .get_account(mint)?
+        .ok_or_else(|| anyhow!("mint {mint} not found"))?
+        .owner)
+}
+
+fn fetch_pair(rpc: &RpcClient, token_mint_a: &Pubkey, token_mint_b: &Pubkey) -> Result<Pair> {
+    Ok(Pair {
+        token_mint_a: *token_mint_a,
+        token_mint_b: *token_mint_b,
+        token_program_a: fetch_token_program(rpc, token_mint_a)?,
+        token_program_b: fetch_token_program(rpc, token_mint_b)?,
+    })
+}
+
+/// 读取链上的Clock系统变量，报价的价格与过期按链上时间而非本地时钟计算
+fn fetch_clock(rpc: &RpcClient) -> Result<Clock> {
+    let account = rpc
+        .get_account(&sysvar::clock::ID)?
+        .ok_or_else(|| anyhow!("clock sysvar not found"))?;
+    bincode::deserialize(&account.data).context("failed to decode clock sysvar")
+}
+
+/// 报价的JSON表示，数量均为最小单位
+fn offer_json(address: &Pubkey, offer: &Offer, now: i64) -> serde_json::Value {
+    json!({
+        "offer": address.to_string(),
+        "offer_id": offer.offer_id,
+        "maker": offer.maker.to_string(),
+        "token_mint_a": offer.token_mint_a.to_string(),
+        "token_mint_b": offer.token_mint_b.to_string(),
+        "token_a_offered_amount": offer.token_a_offered_amount,
+        "token_b_wanted_amount": offer.token_b_wanted_amount,
+        "token_a_remaining_amount": offer.token_a_remaining_amount,
+        "token_b_remaining_amount": offer.current_token_b_remaining_amount(now).ok(),
+        "expires_at": offer.expires_at,
+        "allowed_taker": offer.allowed_taker.map(|taker| taker.to_string()),
+        "revision": offer.revision,
+        "is_basket": offer.is_basket(),
+        "is_arbitrated": offer.is_arbitrated(),
+        "is_cancelled": offer.is_cancelled,
+        "is_expired": offer.is_expired(now),
+    })
+}
@@ -0,0 +1,843 @@
+//! 报价相关账户地址的推导与指令构造
+//!
+//! 地址推导与程序中`MakeOffer`等账户结构的seeds保持一致
+
+use anchor_lang::{
+    prelude::{Clock, Pubkey},
+    solana_program::instruction::Instruction,
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token::spl_token::native_mint,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{TransferFee, TransferFeeConfig},
+            transfer_hook::TransferHook,
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint,
+    },
+};
+use anyhow::{anyhow, bail, Result};
+use swap::{FeeSide, Offer, SwapConfig};
+
+/// 全局配置账户地址，seeds = [b"config"]
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &swap::ID).0
+}
+
+/// 创建者档案账户地址，seeds = [b"maker_profile", maker]
+pub fn maker_profile_address(maker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"maker_profile", maker.as_ref()], &swap::ID).0
+}
+
+/// 报价账户地址，seeds = [b"offer", maker, offer_id(小端序)]
+pub fn offer_address(maker: &Pubkey, offer_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"offer", maker.as_ref(), &offer_id.to_le_bytes()],
+        &swap::ID,
+    )
+    .0
+}
+
+/// 报价托管账户地址，即报价账户持有代币A的关联代币账户
+pub fn vault_address(offer: &Pubkey, token_mint_a: &Pubkey, token_program_a: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(offer, token_mint_a, token_program_a)
+}
+
+/// 记录事件的事件权限账户地址
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &swap::ID).0
+}
+
+/// 代币是否为原生SOL（不经过包装，直接托管lamports）
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == native_mint::ID
+}
+
+/// 用户在某种代币上的关联代币账户，原生SOL不使用代币账户
+fn token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Option<Pubkey> {
+    (!is_native_mint(mint))
+        .then(|| get_associated_token_address_with_program_id(owner, mint, token_program))
+}
+
+/// 一个报价的交易对及其各自的代币程序
+#[derive(Clone, Copy)]
+pub struct Pair {
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+}
+
+/// 创建报价的参数
+pub struct MakeOfferArgs {
+    pub token_a_offered_amount: u64,
+    pub token_b_wanted_amount: u64,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+}
+
+/// 构造以固定价格出售代币A的make_offer指令
+///
+/// `offer_id`须为创建者档案中的下一个报价ID，首次创建报价时为0
+pub fn make_offer_instruction(
+    maker: &Pubkey,
+    offer_id: u64,
+    pair: &Pair,
+    args: &MakeOfferArgs,
+) -> Instruction {
+    let offer = offer_address(maker, offer_id);
+    let native_a = is_native_mint(&pair.token_mint_a);
+    Instruction {
+        program_id: swap::ID,
+        accounts: swap::accounts::MakeOffer {
+            maker: *maker,
+            token_mint_a: pair.token_mint_a,
+            token_mint_b: pair.token_mint_b,
+            maker_token_account_a: token_account(maker, &pair.token_mint_a, &pair.token_program_a),
+            config: config_address(),
+            maker_profile: maker_profile_address(maker),
+            offer,
+            vault: (!native_a)
+                .then(|| vault_address(&offer, &pair.token_mint_a, &pair.token_program_a)),
+            system_program: system_program::ID,
+            token_program_a: pair.token_program_a,
+            token_program_b: pair.token_program_b,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: swap::ID,
+        }
+        .to_account_metas(None),
+        data: swap::instruction::MakeOffer {
+            token_a_offered_amount: args.token_a_offered_amount,
+            token_b_wanted_amount: args.token_b_wanted_amount,
+            expires_at: args.expires_at,
+            allowed_taker: args.allowed_taker,
+            taker_merkle_root: None,
+            extra_legs_a: Vec::new(),
+            extra_legs_b: Vec::new(),
+            dutch_auction: None,
+            vesting: None,
+            arbiter: None,
+            dispute_deadline: None,
+        }
+        .data(),
+    }
+}
+
+/// 成交报价的参数
+pub struct TakeOfferArgs {
+    pub token_b_fill_amount: u64,
+    pub expected_token_a_amount: u64,
+    pub max_token_b_amount: u64,
+}
+
+/// 按链上时间计算的一次成交的数量
+#[derive(Debug, PartialEq, Eq)]
+pub struct TakeQuote {
+    /// 本次支付的代币B数量
+    pub token_b_fill_amount: u64,
+    /// 接受者应收到的代币A数量（已扣除从代币A收取的协议手续费和代币A的转账手续费）
+    pub token_a_amount: u64,
+    /// 接受者应支付的代币B数量（含代币B的转账手续费）
+    pub token_b_amount: u64,
+}
+
+/// 按链上时钟计算成交报价的数量
+///
+/// 未指定或超过当前剩余数量时按剩余数量成交。代币A的数量扣除转给接受者时的
+/// Token-2022转账手续费，与程序按接受者余额变化检查的数量一致；
+/// 代币B的数量计入协议手续费和创建者所得两笔转账各自的Token-2022转账手续费，
+/// 可直接用作成交的滑点上下限。`mint_a_data`与`mint_b_data`为两种代币的Mint账户数据
+pub fn quote_take(
+    offer: &Offer,
+    config: &SwapConfig,
+    pair: &Pair,
+    mint_a_data: &[u8],
+    mint_b_data: &[u8],
+    token_b_fill_amount: Option<u64>,
+    clock: &Clock,
+) -> Result<TakeQuote> {
+    let now = clock.unix_timestamp;
+    let token_b_remaining_amount = offer
+        .current_token_b_remaining_amount(now)
+        .map_err(|error| anyhow!("{error}"))?;
+    let token_b_fill_amount = token_b_fill_amount.map_or(token_b_remaining_amount, |amount| {
+        amount.min(token_b_remaining_amount)
+    });
+    let token_a_released = offer
+        .token_a_for_fill(token_b_fill_amount, now)
+        .map_err(|error| anyhow!("{error}"))?;
+
+    let fee_amount = match config.fee_side {
+        FeeSide::TokenA => token_a_released,
+        FeeSide::TokenB => token_b_fill_amount,
+    };
+    let fee = config
+        .protocol_fee(fee_amount)
+        .map_err(|error| anyhow!("{error}"))?;
+    let (fee_a, fee_b) = match config.fee_side {
+        FeeSide::TokenA => (fee, 0),
+        FeeSide::TokenB => (0, fee),
+    };
+
+    let mut token_b_amount = 0u64;
+    for amount in [fee_b, token_b_fill_amount - fee_b] {
+        if amount > 0 {
+            token_b_amount = token_b_amount
+                .checked_add(gross_up_for_transfer_fee(
+                    &pair.token_program_b,
+                    mint_b_data,
+                    clock.epoch,
+                    amount,
+                )?)
+                .ok_or_else(|| anyhow!("token B amount overflows"))?;
+        }
+    }
+
+    // 托管账户转给接受者的一笔转账由接受者承担代币A的转账手续费
+    let token_a_transferred = token_a_released - fee_a;
+    let token_a_transfer_fee =
+        match epoch_transfer_fee(&pair.token_program_a, mint_a_data, clock.epoch)? {
+            Some(transfer_fee) => transfer_fee
+                .calculate_fee(token_a_transferred)
+                .ok_or_else(|| anyhow!("transfer fee overflows"))?,
+            None => 0,
+        };
+
+    Ok(TakeQuote {
+        token_b_fill_amount,
+        token_a_amount: token_a_transferred - token_a_transfer_fee,
+        token_b_amount,
+    })
+}
+
+/// 接收方实际到账`net_amount`所需的转出数量，与程序中的计算一致
+///
+/// 非Token-2022代币或未启用TransferFee扩展时直接返回`net_amount`
+fn gross_up_for_transfer_fee(
+    token_program: &Pubkey,
+    mint_data: &[u8],
+    epoch: u64,
+    net_amount: u64,
+) -> Result<u64> {
+    match epoch_transfer_fee(token_program, mint_data, epoch)? {
+        Some(transfer_fee) => transfer_fee
+            .calculate_pre_fee_amount(net_amount)
+            .ok_or_else(|| anyhow!("transfer fee overflows")),
+        None => Ok(net_amount),
+    }
+}
+
+/// 代币在给定epoch生效的Token-2022转账手续费设置
+///
+/// 非Token-2022代币或未启用TransferFee扩展时返回None
+fn epoch_transfer_fee(
+    token_program: &Pubkey,
+    mint_data: &[u8],
+    epoch: u64,
+) -> Result<Option<TransferFee>> {
+    if *token_program != spl_token_2022::ID {
+        return Ok(None);
+    }
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+    Ok(mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|transfer_fee_config| *transfer_fee_config.get_epoch_fee(epoch)))
+}
+
+/// 检查报价能否用take_offer_instruction构造的指令成交
+///
+/// 该指令不传NFT元数据与创作者版税账户、Merkle证明和TransferHook额外账户，
+/// 需要这些账户的报价直接报错，而不是发送注定失败的交易
+pub fn check_take_supported(
+    offer: &Offer,
+    pair: &Pair,
+    mint_a_data: &[u8],
+    mint_b_data: &[u8],
+) -> Result<()> {
+    if offer.is_basket() {
+        bail!("basket offers are not supported by swap-cli");
+    }
+    if offer.taker_merkle_root.is_some() {
+        bail!("merkle-gated offers need a taker proof, which swap-cli does not build");
+    }
+    let mint_a = StateWithExtensions::<Mint>::unpack(mint_a_data)?;
+    if mint_a.base.decimals == 0 && mint_a.base.supply == 1 {
+        bail!("NFT offers need creator royalty accounts, which swap-cli does not build");
+    }
+    for (mint, token_program, mint_data) in [
+        (&pair.token_mint_a, &pair.token_program_a, mint_a_data),
+        (&pair.token_mint_b, &pair.token_program_b, mint_b_data),
+    ] {
+        if *token_program == spl_token_2022::ID
+            && StateWithExtensions::<Mint>::unpack(mint_data)?
+                .get_extension::<TransferHook>()
+                .is_ok_and(|transfer_hook| {
+                    Option::<Pubkey>::from(transfer_hook.program_id).is_some()
+                })
+        {
+            bail!(
+                "mint {mint} has a transfer hook, whose extra accounts swap-cli does not resolve"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 构造take_offer指令
+///
+/// 按全局配置的收费方向传入手续费接收者的账户；报价的当前修订号作为预期修订号，
+/// 报价在此之后被修改时成交失败。不传NFT元数据、Merkle证明和TransferHook额外账户，
+/// 构造前应先用`check_take_supported`检查报价
+pub fn take_offer_instruction(
+    taker: &Pubkey,
+    offer: &Offer,
+    config: &SwapConfig,
+    pair: &Pair,
+    args: &TakeOfferArgs,
+) -> Instruction {
+    let offer_address = offer_address(&offer.maker, offer.offer_id);
+
+    let (mut fee_token_account, mut fee_recipient) = (None, None);
+    if config.fee_bps > 0 {
+        let fee_mint = config.fee_mint(&pair.token_mint_a, &pair.token_mint_b);
+        let fee_token_program = if fee_mint == pair.token_mint_a {
+            pair.token_program_a
+        } else {
+            pair.token_program_b
+        };
+        if is_native_mint(&fee_mint) {
+            fee_recipient = Some(config.fee_recipient);
+        } else {
+            fee_token_account = token_account(&config.fee_recipient, &fee_mint, &fee_token_program);
+        }
+    }
+
+    Instruction {
+        program_id: swap::ID,
+        accounts: swap::accounts::TakeOffer {
+            taker: *taker,
+            maker: offer.maker,
+            token_mint_a: pair.token_mint_a,
+            token_mint_b: pair.token_mint_b,
+            taker_token_account_a: token_account(taker, &pair.token_mint_a, &pair.token_program_a),
+            taker_token_account_b: token_account(taker, &pair.token_mint_b, &pair.token_program_b),
+            maker_token_account_b: token_account(
+                &offer.maker,
+                &pair.token_mint_b,
+                &pair.token_program_b,
+            ),
+            offer: offer_address,
+            maker_profile: maker_profile_address(&offer.maker),
+            vault: (!is_native_mint(&pair.token_mint_a))
+                .then(|| vault_address(&offer_address, &pair.token_mint_a, &pair.token_program_a)),
+            config: config_address(),
+            fee_token_account,
+            fee_recipient,
+            referrer_token_account: None,
+            nft_metadata: None,
+            system_program: system_program::ID,
+            token_program_a: pair.token_program_a,
+            token_program_b: pair.token_program_b,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: swap::ID,
+        }
+        .to_account_metas(None),
+        data: swap::instruction::TakeOffer {
+            token_b_fill_amount: args.token_b_fill_amount,
+            taker_proof: Vec::new(),
+            expected_revision: offer.revision,
+            expected_token_a_amount: args.expected_token_a_amount,
+            max_token_b_amount: args.max_token_b_amount,
+            referral_bps: 0,
+        }
+        .data(),
+    }
+}
+
+/// 构造cancel_offer指令
+///
+/// `with_vault`为false时不传托管账户和创建者代币账户（托管账户已关闭时使用）
+pub fn cancel_offer_instruction(
+    offer: &Offer,
+    token_program_a: &Pubkey,
+    with_vault: bool,
+) -> Instruction {
+    let offer_address = offer_address(&offer.maker, offer.offer_id);
+    let with_vault = with_vault && !is_native_mint(&offer.token_mint_a);
+    Instruction {
+        program_id: swap::ID,
+        accounts: swap::accounts::CancelOffer {
+            maker: offer.maker,
+            token_mint_a: offer.token_mint_a,
+            maker_token_account_a: with_vault
+                .then(|| token_account(&offer.maker, &offer.token_mint_a, token_program_a))
+                .flatten(),
+            offer: offer_address,
+            maker_profile: maker_profile_address(&offer.maker),
+            vault: with_vault
+                .then(|| vault_address(&offer_address, &offer.token_mint_a, token_program_a)),
+            system_program: system_program::ID,
+            token_program_a: *token_program_a,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: swap::ID,
+        }
+        .to_account_metas(None),
+        data: swap::instruction::CancelOffer {
+            _offer_id: offer.offer_id,
+        }
+        .data(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_lang::AnchorDeserialize;
+    use anchor_spl::token_2022::spl_token_2022::extension::{
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    };
+    use swap::{DutchAuction, MintListMode};
+
+    use super::*;
+
+    const NOW: i64 = 1_000;
+
+    fn pair(token_program: Pubkey) -> Pair {
+        Pair {
+            token_mint_a: Pubkey::new_unique(),
+            token_mint_b: Pubkey::new_unique(),
+            token_program_a: token_program,
+            token_program_b: token_program,
+        }
+    }
+
+    fn offer(pair: &Pair, token_a_amount: u64, token_b_amount: u64) -> Offer {
+        Offer {
+            offer_id: 7,
+            maker: Pubkey::new_unique(),
+            token_mint_a: pair.token_mint_a,
+            token_mint_b: pair.token_mint_b,
+            token_b_wanted_amount: token_b_amount,
+            token_a_offered_amount: token_a_amount,
+            token_a_remaining_amount: token_a_amount,
+            token_b_remaining_amount: token_b_amount,
+            expires_at: None,
+            allowed_taker: None,
+            taker_merkle_root: None,
+            extra_legs_a: Vec::new(),
+            extra_legs_b: Vec::new(),
+            dutch_auction: None,
+            revision: 3,
+            vesting: None,
+            vesting_beneficiary: None,
+            token_a_vesting_amount: 0,
+            token_a_claimed_amount: 0,
+            arbiter: None,
+            dispute_deadline: None,
+            bump: 255,
+            is_cancelled: false,
+        }
+    }
+
+    fn config(fee_bps: u16, fee_side: FeeSide) -> SwapConfig {
+        SwapConfig {
+            admin: Pubkey::new_unique(),
+            fee_bps,
+            fee_recipient: Pubkey::new_unique(),
+            fee_side,
+            max_referral_bps: 0,
+            paused: false,
+            mint_list_mode: MintListMode::Disabled,
+            mint_list: Vec::new(),
+            bump: 255,
+        }
+    }
+
+    fn clock(unix_timestamp: i64) -> Clock {
+        Clock {
+            unix_timestamp,
+            ..Clock::default()
+        }
+    }
+
+    /// 带TransferFee扩展的Token-2022 Mint账户数据
+    fn transfer_fee_mint_data(transfer_fee_basis_points: u16) -> Vec<u8> {
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let mut data = vec![0; space];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: transfer_fee_basis_points.into(),
+        };
+        let transfer_fee_config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+        transfer_fee_config.older_transfer_fee = transfer_fee;
+        transfer_fee_config.newer_transfer_fee = transfer_fee;
+        mint.base = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        data
+    }
+
+    /// 不带扩展的Mint账户数据
+    fn mint_data(decimals: u8, supply: u64) -> Vec<u8> {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                decimals,
+                supply,
+                is_initialized: true,
+                ..Mint::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    /// 带TransferHook扩展的Token-2022 Mint账户数据
+    fn transfer_hook_mint_data() -> Vec<u8> {
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook])
+                .unwrap();
+        let mut data = vec![0; space];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let transfer_hook = mint.init_extension::<TransferHook>(true).unwrap();
+        transfer_hook.program_id = Some(Pubkey::new_unique()).try_into().unwrap();
+        mint.base = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn addresses_follow_program_seeds() {
+        let maker = Pubkey::new_unique();
+        let (expected_offer, _) = Pubkey::find_program_address(
+            &[b"offer", maker.as_ref(), &7u64.to_le_bytes()],
+            &swap::ID,
+        );
+        assert_eq!(offer_address(&maker, 7), expected_offer);
+        assert_ne!(offer_address(&maker, 7), offer_address(&maker, 8));
+        assert_eq!(
+            config_address(),
+            Pubkey::find_program_address(&[b"config"], &swap::ID).0
+        );
+        assert_eq!(
+            maker_profile_address(&maker),
+            Pubkey::find_program_address(&[b"maker_profile", maker.as_ref()], &swap::ID).0
+        );
+
+        let mint = Pubkey::new_unique();
+        assert_eq!(
+            vault_address(&expected_offer, &mint, &spl_token_2022::ID),
+            get_associated_token_address_with_program_id(
+                &expected_offer,
+                &mint,
+                &spl_token_2022::ID
+            )
+        );
+    }
+
+    #[test]
+    fn make_offer_instruction_accounts() {
+        let maker = Pubkey::new_unique();
+        let pair = pair(spl_token_2022::ID);
+        let instruction = make_offer_instruction(
+            &maker,
+            2,
+            &pair,
+            &MakeOfferArgs {
+                token_a_offered_amount: 100,
+                token_b_wanted_amount: 200,
+                expires_at: Some(NOW),
+                allowed_taker: None,
+            },
+        );
+
+        let accounts = &instruction.accounts;
+        assert_eq!(accounts.len(), 14);
+        assert_eq!(accounts[0].pubkey, maker);
+        assert!(accounts[0].is_signer && accounts[0].is_writable);
+        assert_eq!(
+            accounts[3].pubkey,
+            get_associated_token_address_with_program_id(
+                &maker,
+                &pair.token_mint_a,
+                &spl_token_2022::ID
+            )
+        );
+        assert_eq!(accounts[6].pubkey, offer_address(&maker, 2));
+        assert!(accounts[6].is_writable);
+        assert_eq!(
+            accounts[7].pubkey,
+            vault_address(
+                &offer_address(&maker, 2),
+                &pair.token_mint_a,
+                &spl_token_2022::ID
+            )
+        );
+        assert_eq!(accounts[12].pubkey, event_authority_address());
+
+        let args = swap::instruction::MakeOffer::try_from_slice(&instruction.data[8..]).unwrap();
+        assert_eq!(args.token_a_offered_amount, 100);
+        assert_eq!(args.token_b_wanted_amount, 200);
+        assert_eq!(args.expires_at, Some(NOW));
+    }
+
+    #[test]
+    fn make_offer_instruction_omits_token_accounts_for_native_sol() {
+        let maker = Pubkey::new_unique();
+        let pair = Pair {
+            token_mint_a: native_mint::ID,
+            ..pair(anchor_spl::token::ID)
+        };
+        let instruction = make_offer_instruction(
+            &maker,
+            0,
+            &pair,
+            &MakeOfferArgs {
+                token_a_offered_amount: 100,
+                token_b_wanted_amount: 200,
+                expires_at: None,
+                allowed_taker: None,
+            },
+        );
+
+        // 未传入的可选账户以程序ID占位
+        assert_eq!(instruction.accounts[3].pubkey, swap::ID);
+        assert_eq!(instruction.accounts[7].pubkey, swap::ID);
+    }
+
+    #[test]
+    fn take_offer_instruction_passes_fee_account_for_fee_side() {
+        let taker = Pubkey::new_unique();
+        let pair = pair(anchor_spl::token::ID);
+        let offer = offer(&pair, 100, 200);
+        let args = TakeOfferArgs {
+            token_b_fill_amount: 50,
+            expected_token_a_amount: 25,
+            max_token_b_amount: 50,
+        };
+
+        let config = config(0, FeeSide::TokenB);
+        let instruction = take_offer_instruction(&taker, &offer, &config, &pair, &args);
+        assert_eq!(instruction.accounts.len(), 21);
+        assert_eq!(instruction.accounts[11].pubkey, swap::ID);
+        assert_eq!(instruction.accounts[12].pubkey, swap::ID);
+
+        for (fee_side, fee_mint) in [
+            (FeeSide::TokenA, pair.token_mint_a),
+            (FeeSide::TokenB, pair.token_mint_b),
+        ] {
+            let config = self::config(30, fee_side);
+            let instruction = take_offer_instruction(&taker, &offer, &config, &pair, &args);
+            let accounts = &instruction.accounts;
+            assert_eq!(accounts[0].pubkey, taker);
+            assert!(accounts[0].is_signer);
+            assert_eq!(accounts[1].pubkey, offer.maker);
+            assert_eq!(
+                accounts[7].pubkey,
+                offer_address(&offer.maker, offer.offer_id)
+            );
+            assert_eq!(
+                accounts[11].pubkey,
+                get_associated_token_address_with_program_id(
+                    &config.fee_recipient,
+                    &fee_mint,
+                    &anchor_spl::token::ID
+                )
+            );
+            assert!(accounts[11].is_writable);
+        }
+
+        let data = swap::instruction::TakeOffer::try_from_slice(&instruction.data[8..]).unwrap();
+        assert_eq!(data.token_b_fill_amount, 50);
+        assert_eq!(data.expected_revision, offer.revision);
+        assert_eq!(data.expected_token_a_amount, 25);
+        assert_eq!(data.max_token_b_amount, 50);
+    }
+
+    #[test]
+    fn cancel_offer_instruction_without_vault() {
+        let pair = pair(anchor_spl::token::ID);
+        let offer = offer(&pair, 100, 200);
+        let offer_address = offer_address(&offer.maker, offer.offer_id);
+
+        let instruction = cancel_offer_instruction(&offer, &pair.token_program_a, true);
+        assert_eq!(instruction.accounts[0].pubkey, offer.maker);
+        assert!(instruction.accounts[0].is_signer);
+        assert_eq!(instruction.accounts[3].pubkey, offer_address);
+        assert_eq!(
+            instruction.accounts[5].pubkey,
+            vault_address(&offer_address, &pair.token_mint_a, &pair.token_program_a)
+        );
+
+        let instruction = cancel_offer_instruction(&offer, &pair.token_program_a, false);
+        assert_eq!(instruction.accounts[2].pubkey, swap::ID);
+        assert_eq!(instruction.accounts[5].pubkey, swap::ID);
+    }
+
+    #[test]
+    fn quote_take_defaults_to_remaining_amount() {
+        let pair = pair(anchor_spl::token::ID);
+        let offer = offer(&pair, 100, 200);
+        let config = config(100, FeeSide::TokenA);
+
+        let quote = quote_take(&offer, &config, &pair, &[], &[], None, &clock(NOW)).unwrap();
+        assert_eq!(
+            quote,
+            TakeQuote {
+                token_b_fill_amount: 200,
+                token_a_amount: 99,
+                token_b_amount: 200,
+            }
+        );
+
+        // 超过剩余数量时按剩余数量成交
+        let quote = quote_take(&offer, &config, &pair, &[], &[], Some(500), &clock(NOW)).unwrap();
+        assert_eq!(quote.token_b_fill_amount, 200);
+
+        let quote = quote_take(&offer, &config, &pair, &[], &[], Some(50), &clock(NOW)).unwrap();
+        assert_eq!(quote.token_b_fill_amount, 50);
+        assert_eq!(quote.token_a_amount, 25);
+    }
+
+    #[test]
+    fn quote_take_uses_chain_clock_for_dutch_auctions() {
+        let pair = pair(anchor_spl::token::ID);
+        let mut offer = offer(&pair, 100, 200);
+        offer.dutch_auction = Some(DutchAuction {
+            start_time: NOW,
+            end_time: NOW + 100,
+            floor_token_b_amount: 100,
+        });
+        let config = config(0, FeeSide::TokenB);
+
+        let quote = quote_take(&offer, &config, &pair, &[], &[], None, &clock(NOW)).unwrap();
+        assert_eq!(quote.token_b_fill_amount, 200);
+        let quote = quote_take(&offer, &config, &pair, &[], &[], None, &clock(NOW + 50)).unwrap();
+        assert_eq!(quote.token_b_fill_amount, 150);
+        assert_eq!(quote.token_a_amount, 100);
+    }
+
+    #[test]
+    fn quote_take_includes_token_b_transfer_fees() {
+        let pair = pair(spl_token_2022::ID);
+        let offer = offer(&pair, 100, 10_000);
+        let mint_a_data = transfer_fee_mint_data(0);
+        let mint_b_data = transfer_fee_mint_data(100);
+
+        // 无协议手续费时只有转给创建者的一笔转账
+        let config = config(0, FeeSide::TokenB);
+        let quote = quote_take(
+            &offer,
+            &config,
+            &pair,
+            &mint_a_data,
+            &mint_b_data,
+            None,
+            &clock(NOW),
+        )
+        .unwrap();
+        assert_eq!(quote.token_b_amount, 10_102);
+
+        // 协议手续费与创建者所得分两笔转账，各自承担转账手续费
+        let config = self::config(100, FeeSide::TokenB);
+        let quote = quote_take(
+            &offer,
+            &config,
+            &pair,
+            &mint_a_data,
+            &mint_b_data,
+            None,
+            &clock(NOW),
+        )
+        .unwrap();
+        assert_eq!(quote.token_b_amount, 102 + 10_000);
+        assert_eq!(quote.token_a_amount, 100);
+    }
+
+    #[test]
+    fn quote_take_excludes_token_a_transfer_fees() {
+        let pair = pair(spl_token_2022::ID);
+        let offer = offer(&pair, 10_000, 100);
+        let mint_a_data = transfer_fee_mint_data(100);
+        let mint_b_data = transfer_fee_mint_data(0);
+
+        // 接受者按扣除转账手续费后的到账数量检查滑点
+        let config = config(0, FeeSide::TokenB);
+        let quote = quote_take(
+            &offer,
+            &config,
+            &pair,
+            &mint_a_data,
+            &mint_b_data,
+            None,
+            &clock(NOW),
+        )
+        .unwrap();
+        assert_eq!(quote.token_a_amount, 9_900);
+        assert_eq!(quote.token_b_amount, 100);
+
+        // 协议手续费单独转出，转给接受者的部分再扣除转账手续费
+        let config = self::config(100, FeeSide::TokenA);
+        let quote = quote_take(
+            &offer,
+            &config,
+            &pair,
+            &mint_a_data,
+            &mint_b_data,
+            None,
+            &clock(NOW),
+        )
+        .unwrap();
+        assert_eq!(quote.token_a_amount, 9_900 - 99);
+    }
+
+    #[test]
+    fn check_take_supported_rejects_offers_needing_extra_accounts() {
+        let pair = pair(anchor_spl::token::ID);
+        let plain_mint_data = mint_data(6, 1_000_000);
+        let offer = offer(&pair, 100, 200);
+        check_take_supported(&offer, &pair, &plain_mint_data, &plain_mint_data).unwrap();
+
+        let mut merkle_offer = offer.clone();
+        merkle_offer.taker_merkle_root = Some([7; 32]);
+        assert!(
+            check_take_supported(&merkle_offer, &pair, &plain_mint_data, &plain_mint_data).is_err()
+        );
+
+        assert!(check_take_supported(&offer, &pair, &mint_data(0, 1), &plain_mint_data).is_err());
+
+        let pair = self::pair(spl_token_2022::ID);
+        let hook_mint_data = transfer_hook_mint_data();
+        let offer = self::offer(&pair, 100, 200);
+        assert!(check_take_supported(&offer, &pair, &plain_mint_data, &hook_mint_data).is_err());
+        check_take_supported(
+            &offer,
+            &pair,
+            &transfer_fee_mint_data(0),
+            &transfer_fee_mint_data(0),
+        )
+        .unwrap();
+    }
+}
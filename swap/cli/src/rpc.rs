@@ -0,0 +1,187 @@
+//! 最小的Solana JSON-RPC客户端，只实现命令行工具用到的几个方法
+
+use std::{str::FromStr, thread, time::Duration};
+
+use anchor_lang::{prelude::Pubkey, solana_program::hash::Hash};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use solana_transaction::Transaction;
+
+/// 等待交易确认时的轮询间隔
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// 等待交易确认的最大轮询次数（约60秒）
+const CONFIRM_MAX_POLLS: u32 = 120;
+
+/// 链上账户的所有者与数据
+pub struct RpcAccount {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// `getProgramAccounts`的memcmp过滤条件
+pub struct Memcmp {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+/// 通过HTTP访问Solana RPC节点的客户端
+pub struct RpcClient {
+    url: String,
+    commitment: String,
+}
+
+impl RpcClient {
+    pub fn new(url: String, commitment: String) -> Self {
+        Self { url, commitment }
+    }
+
+    /// 发送一次JSON-RPC请求，返回`result`字段
+    fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = ureq::post(&self.url)
+            .send_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .with_context(|| format!("{method} request to {} failed", self.url))?
+            .into_json()?;
+
+        if let Some(error) = response.get("error") {
+            bail!("{method} failed: {error}");
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("{method} returned no result"))
+    }
+
+    /// 读取账户，账户不存在时返回None
+    pub fn get_account(&self, address: &Pubkey) -> Result<Option<RpcAccount>> {
+        let result = self.request(
+            "getAccountInfo",
+            json!([
+                address.to_string(),
+                { "encoding": "base64", "commitment": self.commitment },
+            ]),
+        )?;
+        match result.get("value") {
+            Some(Value::Null) | None => Ok(None),
+            Some(account) => parse_account(account).map(Some),
+        }
+    }
+
+    /// 读取程序拥有的、满足全部过滤条件的账户
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Memcmp],
+    ) -> Result<Vec<(Pubkey, RpcAccount)>> {
+        let filters: Vec<Value> = filters
+            .iter()
+            .map(|filter| {
+                json!({
+                    "memcmp": {
+                        "offset": filter.offset,
+                        "bytes": BASE64.encode(&filter.bytes),
+                        "encoding": "base64",
+                    }
+                })
+            })
+            .collect();
+        let result = self.request(
+            "getProgramAccounts",
+            json!([
+                program_id.to_string(),
+                {
+                    "encoding": "base64",
+                    "commitment": self.commitment,
+                    "filters": filters,
+                },
+            ]),
+        )?;
+
+        result
+            .as_array()
+            .ok_or_else(|| anyhow!("getProgramAccounts returned no accounts"))?
+            .iter()
+            .map(|keyed_account| {
+                let address = keyed_account["pubkey"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("account without pubkey"))?;
+                Ok((
+                    Pubkey::from_str(address)?,
+                    parse_account(&keyed_account["account"])?,
+                ))
+            })
+            .collect()
+    }
+
+    /// 获取最新区块哈希
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result = self.request(
+            "getLatestBlockhash",
+            json!([{ "commitment": self.commitment }]),
+        )?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("getLatestBlockhash returned no blockhash"))?;
+        Ok(Hash::from_str(blockhash)?)
+    }
+
+    /// 发送已签名的交易并等待确认，返回交易签名
+    pub fn send_and_confirm(&self, transaction: &Transaction) -> Result<String> {
+        let serialized = bincode::serialize(transaction)?;
+        let signature = self
+            .request(
+                "sendTransaction",
+                json!([
+                    BASE64.encode(serialized),
+                    { "encoding": "base64", "preflightCommitment": self.commitment },
+                ]),
+            )?
+            .as_str()
+            .ok_or_else(|| anyhow!("sendTransaction returned no signature"))?
+            .to_string();
+
+        for _ in 0..CONFIRM_MAX_POLLS {
+            let result = self.request("getSignatureStatuses", json!([[signature]]))?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    bail!("transaction {signature} failed: {}", status["err"]);
+                }
+                let confirmation = status["confirmationStatus"].as_str().unwrap_or_default();
+                if commitment_level(confirmation) >= commitment_level(&self.commitment) {
+                    return Ok(signature);
+                }
+            }
+            thread::sleep(CONFIRM_POLL_INTERVAL);
+        }
+        bail!("timed out waiting for transaction {signature} to be confirmed")
+    }
+}
+
+/// 承诺级别的先后顺序，未知级别视为最低
+fn commitment_level(commitment: &str) -> u8 {
+    match commitment {
+        "finalized" => 2,
+        "confirmed" => 1,
+        _ => 0,
+    }
+}
+
+/// 解析base64编码的账户信息
+fn parse_account(account: &Value) -> Result<RpcAccount> {
+    let owner = account["owner"]
+        .as_str()
+        .ok_or_else(|| anyhow!("account without owner"))?;
+    let data = account["data"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("account without base64 data"))?;
+    Ok(RpcAccount {
+        owner: Pubkey::from_str(owner)?,
+        data: BASE64.decode(data)?,
+    })
+}
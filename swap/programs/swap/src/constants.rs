@@ -4,3 +4,31 @@ use anchor_lang::prelude::*;
 pub const SEED: &str = "anchor";
 
 pub const ANCHOR_DISCRIMINATOR: usize = 8;
+
+/// 基点分母（10000基点 = 100%）
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// 协议手续费上限（1000基点 = 10%）
+#[constant]
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// 一篮子报价每一侧最多的额外交易腿数量
+pub const MAX_BASKET_LEGS: usize = 4;
+
+/// 每个交易对市场最多索引的报价数量
+pub const MAX_MARKET_OFFERS: usize = 32;
+
+/// 全局代币名单最多包含的代币数量
+pub const MAX_MINT_LIST_LEN: usize = 32;
+
+/// 流动性池首次注入时永久锁定的LP代币数量，防止池子被抽空后份额价格被操纵
+#[constant]
+pub const POOL_MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// 流动性池LP代币的精度
+#[constant]
+pub const POOL_LP_DECIMALS: u8 = 9;
+
+/// 签名订单消息的域标签，放在程序ID和订单内容之前
+#[constant]
+pub const SIGNED_ORDER_DOMAIN: &[u8] = b"swap:signed-order";
@@ -17,4 +17,252 @@ pub enum ErrorCode {
     /// 代币Mint地址不匹配
     #[msg("Token mint does not match the offer")]
     WrongTokenMint,
+
+    /// 数量必须大于零
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+
+    /// 成交数量为零或超过报价剩余数量
+    #[msg("Fill amount must be greater than zero and not exceed the remaining amount")]
+    InvalidFillAmount,
+
+    /// 成交数量过小，按比例换算后无法释放任何代币A
+    #[msg("Fill amount is too small to release any offered tokens")]
+    FillAmountTooSmall,
+
+    /// 数值计算溢出
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    /// 过期时间必须晚于当前时间
+    #[msg("Offer expiry must be in the future")]
+    InvalidExpiry,
+
+    /// 报价已过期
+    #[msg("Offer has expired")]
+    OfferExpired,
+
+    /// 报价尚未过期
+    #[msg("Offer has not expired yet")]
+    OfferNotExpired,
+
+    /// 不能同时指定接受者和接受者默克尔根
+    #[msg("Offer can restrict takers by pubkey or by merkle root, not both")]
+    InvalidTakerRestriction,
+
+    /// 接受者不在报价允许的范围内
+    #[msg("Taker is not allowed to take this offer")]
+    TakerNotAllowed,
+
+    /// 调用者不是配置管理员
+    #[msg("Only the config admin can perform this action")]
+    NotAdmin,
+
+    /// 手续费超过上限
+    #[msg("Fee basis points exceed the maximum allowed")]
+    InvalidFeeBps,
+
+    /// 手续费账户与配置不匹配
+    #[msg("Fee token account does not match the config")]
+    WrongFeeAccount,
+
+    /// 接收方实际到账数量与预期不一致（如转账手续费变化）
+    #[msg("Received amount does not match the exact amount expected")]
+    TransferAmountMismatch,
+
+    /// 一篮子报价的额外交易腿超过上限
+    #[msg("Too many basket legs")]
+    TooManyBasketLegs,
+
+    /// 一篮子报价中存在重复的代币Mint
+    #[msg("Basket legs must use distinct mints")]
+    DuplicateBasketMint,
+
+    /// 一篮子报价只能一次全部成交
+    #[msg("Basket offers must be filled in full")]
+    BasketRequiresFullFill,
+
+    /// remaining_accounts中缺少一篮子报价腿所需的账户
+    #[msg("Missing accounts for a basket leg")]
+    MissingBasketAccounts,
+
+    /// 代币账户不是预期的关联代币账户
+    #[msg("Token account is not the expected associated token account")]
+    WrongTokenAccount,
+
+    /// 代币Mint不属于预期的代币程序
+    #[msg("Token mint is not owned by the expected token program")]
+    WrongTokenProgram,
+
+    /// 报价已被创建者修改，与接受者预期的修订号不一致
+    #[msg("Offer was updated since the taker quoted it")]
+    OfferRevisionMismatch,
+
+    /// 接受者实际收到的代币A少于预期
+    #[msg("Taker would receive less token A than expected")]
+    TokenAAmountTooLow,
+
+    /// 接受者实际支付的代币B超过上限
+    #[msg("Taker would pay more token B than the allowed maximum")]
+    TokenBAmountTooHigh,
+
+    /// 荷兰拍卖参数无效
+    #[msg("Dutch auction must end after it starts with a floor below the starting amount")]
+    InvalidDutchAuction,
+
+    /// 市场索引的报价数量已达上限
+    #[msg("Market has no room for more offers")]
+    MarketFull,
+
+    /// 报价已在市场中上架
+    #[msg("Offer is already listed in this market")]
+    OfferAlreadyListed,
+
+    /// 报价未在市场中上架
+    #[msg("Offer is not listed in this market")]
+    OfferNotListed,
+
+    /// 只有固定价格、不限制接受者的普通报价可以上架
+    #[msg("Only fixed-price single-pair offers open to any taker can be listed")]
+    OfferNotListable,
+
+    /// remaining_accounts中的报价账户与市场索引顺序不一致
+    #[msg("Offer accounts do not match the market order")]
+    WrongMarketAccounts,
+
+    /// 市场中没有可成交的报价
+    #[msg("No offer in the market could be filled")]
+    NoMatchingOffers,
+
+    /// 非原生SOL代币缺少所需的代币账户
+    #[msg("Token account is required for a non-native mint")]
+    MissingTokenAccount,
+
+    /// 原生SOL报价不支持该操作
+    #[msg("This operation does not support native SOL offers")]
+    NativeMintNotSupported,
+
+    /// remaining_accounts中缺少报价的账户组
+    #[msg("Not enough accounts for the offers to fill")]
+    MissingOfferAccounts,
+
+    /// 一篮子报价、带解锁计划的报价和默克尔名单限制的报价不能通过批量成交指令成交
+    #[msg("Basket, vesting and merkle-gated offers cannot be filled through take_offers")]
+    OfferNotRoutable,
+
+    /// 缺少有效的创建者订单签名
+    #[msg("Order is not signed by the maker")]
+    InvalidOrderSignature,
+
+    /// 推荐费率超过配置上限
+    #[msg("Referral basis points exceed the configured maximum")]
+    ReferralBpsTooHigh,
+
+    /// NFT元数据账户不是代币A的Metaplex元数据
+    #[msg("Metadata account does not belong to token A")]
+    WrongMetadataAccount,
+
+    /// 代币A为NFT时缺少元数据账户
+    #[msg("Metadata account is required to pay NFT royalties")]
+    MissingMetadataAccount,
+
+    /// remaining_accounts中缺少创作者接收版税的账户
+    #[msg("Royalty account for a verified creator is missing")]
+    MissingRoyaltyAccount,
+
+    /// NFT报价只能通过take_offer成交以支付创作者版税
+    #[msg("NFT offers must be filled through take_offer to pay creator royalties")]
+    NftOfferNotRoutable,
+
+    /// 解锁计划的结束时间必须晚于开始时间
+    #[msg("Vesting end time must be after its start time")]
+    InvalidVestingSchedule,
+
+    /// 带解锁计划的报价只能一次全部成交
+    #[msg("Vesting offers must be filled in full")]
+    VestingRequiresFullFill,
+
+    /// 带解锁计划的报价不支持一篮子和原生SOL代币A
+    #[msg("Vesting offers cannot be baskets or offer native SOL")]
+    InvalidVestingOffer,
+
+    /// 报价已成交，托管的代币A正在向接受者解锁
+    #[msg("Offer is filled and its token A is vesting to the taker")]
+    OfferIsVesting,
+
+    /// 报价没有正在解锁的代币A
+    #[msg("Offer has no token A vesting")]
+    OfferNotVesting,
+
+    /// 签名者不是解锁代币A的接受者
+    #[msg("Signer is not the vesting beneficiary of this offer")]
+    NotVestingBeneficiary,
+
+    /// 当前没有可提取的已解锁代币A
+    #[msg("No vested tokens to claim yet")]
+    NothingToClaim,
+
+    /// 仲裁报价必须指定接受者和未来的争议截止时间，且只支持普通的代币A（不能是原生SOL或NFT）
+    #[msg("Arbitrated offers need an allowed taker, a future dispute deadline and a plain, non-NFT token A leg")]
+    InvalidArbitratedOffer,
+
+    /// 仲裁报价只能由仲裁人或双方共同处置
+    #[msg("Arbitrated offers settle through release_to_taker or refund_to_maker")]
+    OfferIsArbitrated,
+
+    /// 报价没有设置仲裁人
+    #[msg("Offer has no arbiter")]
+    OfferNotArbitrated,
+
+    /// 签名者不是报价的仲裁人
+    #[msg("Signer is not the arbiter of this offer")]
+    NotArbiter,
+
+    /// 需要仲裁人签名，或创建者与接受者共同签名
+    #[msg("Only the arbiter or both parties jointly can resolve this offer")]
+    NotAuthorizedToResolve,
+
+    /// 协议已暂停创建和成交报价
+    #[msg("The protocol is paused")]
+    ProtocolPaused,
+
+    /// 代币不被全局代币名单允许
+    #[msg("Mint is not allowed by the mint list")]
+    MintNotAllowed,
+
+    /// 代币设置了冻结权限、永久代理或默认冻结新账户
+    #[msg("Mint has a freeze authority, permanent delegate or frozen default account state")]
+    DangerousMint,
+
+    /// 代币名单超过长度上限
+    #[msg("Mint list is too long")]
+    MintListTooLong,
+
+    /// 流动性池的代币A地址必须小于代币B地址
+    #[msg("Pool mints must be distinct and ordered with token A before token B")]
+    InvalidPoolMints,
+
+    /// 注入或取出的流动性不足以铸造或兑换出任何代币
+    #[msg("Insufficient liquidity for this operation")]
+    InsufficientLiquidity,
+
+    /// 流动性池操作的结果低于调用者要求的最小数量
+    #[msg("Pool operation output is below the requested minimum")]
+    PoolSlippageExceeded,
+
+    /// 只有程序的升级权限才能初始化全局配置
+    #[msg("Only the program upgrade authority can initialize the config")]
+    NotUpgradeAuthority,
+
+    /// 传入的报价都仍然有效，没有可以清理的市场记录
+    #[msg("None of the given offers can be pruned from the market")]
+    NothingToPrune,
+
+    /// 订单尚未过期，随机数账户还不能关闭
+    #[msg("Order has not expired yet")]
+    OrderNotExpired,
+
+    /// 一篮子报价的额外交易腿不支付版税，不能包含NFT
+    #[msg("NFTs cannot be offered as basket legs")]
+    NftBasketLeg,
 }
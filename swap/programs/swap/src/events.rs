@@ -0,0 +1,220 @@
+//! 报价生命周期事件
+//!
+//! 事件通过`emit_cpi!`以程序自调用指令的形式写入交易，不会像日志一样被截断。
+//! `emit_cpi!`要求作用域内的上下文变量名为`ctx`，指令函数中以`let ctx = &context;`引入
+
+use anchor_lang::prelude::*;
+
+use crate::Offer;
+
+/// 报价创建事件
+#[event]
+pub struct OfferCreated {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址
+    pub token_mint_b: Pubkey,
+    /// 实际托管的代币A数量
+    pub token_a_offered_amount: u64,
+    /// 想要的代币B数量
+    pub token_b_wanted_amount: u64,
+    /// 报价过期的Unix时间戳，None表示永不过期
+    pub expires_at: Option<i64>,
+}
+
+/// 报价修改事件（重新定价、追加或部分取回托管代币）
+#[event]
+pub struct OfferUpdated {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 修改后的报价修订号
+    pub revision: u32,
+    /// 修改后剩余的代币A数量
+    pub token_a_remaining_amount: u64,
+    /// 修改后想要的代币B总数量
+    pub token_b_wanted_amount: u64,
+    /// 修改后剩余的代币B数量
+    pub token_b_remaining_amount: u64,
+}
+
+/// 报价全部成交事件
+#[event]
+pub struct OfferTaken {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 接受者
+    pub taker: Pubkey,
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址
+    pub token_mint_b: Pubkey,
+    /// 本次成交释放的代币A数量（含手续费）
+    pub token_a_amount: u64,
+    /// 本次成交支付的代币B数量（含手续费）
+    pub token_b_amount: u64,
+}
+
+/// 报价部分成交事件
+#[event]
+pub struct OfferPartiallyTaken {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 接受者
+    pub taker: Pubkey,
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址
+    pub token_mint_b: Pubkey,
+    /// 本次成交释放的代币A数量（含手续费）
+    pub token_a_amount: u64,
+    /// 本次成交支付的代币B数量（含手续费）
+    pub token_b_amount: u64,
+    /// 成交后剩余的代币A数量
+    pub token_a_remaining_amount: u64,
+    /// 成交后剩余的代币B数量
+    pub token_b_remaining_amount: u64,
+}
+
+/// 报价撤销事件
+#[event]
+pub struct OfferCancelled {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址
+    pub token_mint_b: Pubkey,
+    /// 退还给创建者的代币A数量
+    pub token_a_refunded_amount: u64,
+}
+
+/// 接受者提取已解锁代币事件
+#[event]
+pub struct VestedTokensClaimed {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 提取代币的接受者
+    pub beneficiary: Pubkey,
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 本次提取的代币A数量
+    pub token_a_amount: u64,
+    /// 累计已提取的代币A数量
+    pub token_a_claimed_amount: u64,
+}
+
+/// 过期报价被回收事件
+#[event]
+pub struct OfferExpired {
+    /// 报价ID
+    pub offer_id: u64,
+    /// 报价创建者
+    pub maker: Pubkey,
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址
+    pub token_mint_b: Pubkey,
+    /// 退还给创建者的代币A数量
+    pub token_a_refunded_amount: u64,
+}
+
+impl OfferCreated {
+    /// 按刚保存的报价生成事件
+    pub fn new(offer: &Offer) -> Self {
+        Self {
+            offer_id: offer.offer_id,
+            maker: offer.maker,
+            token_mint_a: offer.token_mint_a,
+            token_mint_b: offer.token_mint_b,
+            token_a_offered_amount: offer.token_a_offered_amount,
+            token_b_wanted_amount: offer.token_b_wanted_amount,
+            expires_at: offer.expires_at,
+        }
+    }
+}
+
+impl OfferUpdated {
+    /// 按修改后的报价生成事件
+    pub fn new(offer: &Offer) -> Self {
+        Self {
+            offer_id: offer.offer_id,
+            maker: offer.maker,
+            revision: offer.revision,
+            token_a_remaining_amount: offer.token_a_remaining_amount,
+            token_b_wanted_amount: offer.token_b_wanted_amount,
+            token_b_remaining_amount: offer.token_b_remaining_amount,
+        }
+    }
+}
+
+impl OfferTaken {
+    /// 按记录本次成交后的报价生成事件
+    pub fn new(offer: &Offer, taker: Pubkey, token_a_amount: u64, token_b_amount: u64) -> Self {
+        Self {
+            offer_id: offer.offer_id,
+            maker: offer.maker,
+            taker,
+            token_mint_a: offer.token_mint_a,
+            token_mint_b: offer.token_mint_b,
+            token_a_amount,
+            token_b_amount,
+        }
+    }
+}
+
+impl OfferPartiallyTaken {
+    /// 按记录本次成交后的报价生成事件
+    pub fn new(offer: &Offer, taker: Pubkey, token_a_amount: u64, token_b_amount: u64) -> Self {
+        Self {
+            offer_id: offer.offer_id,
+            maker: offer.maker,
+            taker,
+            token_mint_a: offer.token_mint_a,
+            token_mint_b: offer.token_mint_b,
+            token_a_amount,
+            token_b_amount,
+            token_a_remaining_amount: offer.token_a_remaining_amount,
+            token_b_remaining_amount: offer.token_b_remaining_amount,
+        }
+    }
+}
+
+impl OfferCancelled {
+    /// 按撤销前剩余的报价数量生成事件
+    pub fn new(offer: &Offer) -> Self {
+        Self {
+            offer_id: offer.offer_id,
+            maker: offer.maker,
+            token_mint_a: offer.token_mint_a,
+            token_mint_b: offer.token_mint_b,
+            token_a_refunded_amount: offer.token_a_remaining_amount,
+        }
+    }
+}
+
+impl OfferExpired {
+    /// 按回收前剩余的报价数量生成事件
+    pub fn new(offer: &Offer) -> Self {
+        Self {
+            offer_id: offer.offer_id,
+            maker: offer.maker,
+            token_mint_a: offer.token_mint_a,
+            token_mint_b: offer.token_mint_b,
+            token_a_refunded_amount: offer.token_a_remaining_amount,
+        }
+    }
+}
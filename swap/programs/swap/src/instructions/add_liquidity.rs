@@ -0,0 +1,206 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, Pool, SwapConfig};
+
+use super::{check_mint, token_account_balance, transfer_tokens};
+
+/// 向流动性池注入流动性的账户结构
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    /// 流动性提供者（签名者）
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    /// 全局配置账户（协议暂停时不能注入流动性）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    #[account(mint::token_program = token_program_b)]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 流动性池账户
+    #[account(
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        has_one = token_mint_b @ ErrorCode::WrongTokenMint,
+        has_one = lp_mint @ ErrorCode::WrongTokenMint,
+        seeds = [b"pool", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// LP代币的Mint账户
+    #[account(mut, mint::token_program = token_program_a)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// 代币A的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 代币B的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 提供者代币A的关联账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program_a
+    )]
+    pub provider_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 提供者代币B的关联账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program_b
+    )]
+    pub provider_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 提供者LP代币的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = provider,
+        associated_token::mint = lp_mint,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program_a
+    )]
+    pub provider_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+    /// 代币A（及LP代币）的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序接口
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 向流动性池注入流动性
+///
+/// 首次注入按给出的数量存入，LP代币数量为两者的几何平均数并永久锁定最低流动性；
+/// 之后按当前储备比例存入不超过上限的数量，按实际到账数量铸造LP代币。
+/// 代币须仍然通过全局配置的代币检查
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `max_token_a_amount` - 最多存入的代币A数量
+/// * `max_token_b_amount` - 最多存入的代币B数量
+/// * `min_lp_amount` - 至少要铸造的LP代币数量
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn add_liquidity<'info>(
+    context: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
+    max_token_a_amount: u64,
+    max_token_b_amount: u64,
+    min_lp_amount: u64,
+) -> Result<()> {
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+
+    let reserve_a = context.accounts.vault_a.amount;
+    let reserve_b = context.accounts.vault_b.amount;
+    let lp_supply = context.accounts.lp_mint.supply;
+    // LP全部赎回后储备中仍留有永久锁定的最低流动性对应的代币，此时按储备比例注入，
+    // 不能让新的提供者以首次注入的方式独占这部分储备；
+    // 只有一侧有储备（他人直接转入）时无从定价，仍按首次注入处理
+    let is_initial_deposit = lp_supply == 0 && (reserve_a == 0 || reserve_b == 0);
+
+    let (token_a_amount, token_b_amount) = if is_initial_deposit {
+        (max_token_a_amount, max_token_b_amount)
+    } else {
+        Pool::deposit_amounts(reserve_a, reserve_b, max_token_a_amount, max_token_b_amount)?
+    };
+    require!(
+        token_a_amount > 0 && token_b_amount > 0,
+        ErrorCode::InvalidAmount
+    );
+
+    transfer_tokens(
+        &context.accounts.provider_token_account_a,
+        &context.accounts.vault_a,
+        &token_a_amount,
+        &context.accounts.token_mint_a,
+        &context.accounts.provider.to_account_info(),
+        &context.accounts.token_program_a,
+        &[],
+        context.remaining_accounts,
+    )?;
+    transfer_tokens(
+        &context.accounts.provider_token_account_b,
+        &context.accounts.vault_b,
+        &token_b_amount,
+        &context.accounts.token_mint_b,
+        &context.accounts.provider.to_account_info(),
+        &context.accounts.token_program_b,
+        &[],
+        context.remaining_accounts,
+    )?;
+
+    // 以托管账户实际到账的数量为准，Token-2022转账手续费会使其少于转出数量
+    let token_a_received = token_account_balance(&context.accounts.vault_a.to_account_info())?
+        .checked_sub(reserve_a)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let token_b_received = token_account_balance(&context.accounts.vault_b.to_account_info())?
+        .checked_sub(reserve_b)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let liquidity = if is_initial_deposit {
+        Pool::initial_liquidity(token_a_received, token_b_received)?
+    } else {
+        Pool::deposit_liquidity(
+            token_a_received,
+            token_b_received,
+            reserve_a,
+            reserve_b,
+            lp_supply,
+        )?
+    };
+    require_gte!(liquidity, min_lp_amount, ErrorCode::PoolSlippageExceeded);
+
+    let seeds = &[
+        b"pool",
+        context.accounts.pool.token_mint_a.as_ref(),
+        context.accounts.pool.token_mint_b.as_ref(),
+        &[context.accounts.pool.bump],
+    ];
+    let signer_seeds = [&seeds[..]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            context.accounts.token_program_a.to_account_info(),
+            MintTo {
+                mint: context.accounts.lp_mint.to_account_info(),
+                to: context.accounts.provider_lp_token_account.to_account_info(),
+                authority: context.accounts.pool.to_account_info(),
+            },
+            &signer_seeds,
+        ),
+        liquidity,
+    )
+}
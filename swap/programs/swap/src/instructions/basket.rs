@@ -0,0 +1,337 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, OfferLeg, SwapConfig, MAX_BASKET_LEGS};
+
+use super::{check_mint, is_nft, token_account_balance, transfer_tokens, transfer_tokens_exact};
+
+/// 每条额外交易腿在remaining_accounts中占用的账户数量
+///
+/// 一篮子报价的账户组排在remaining_accounts最前面，先代币A侧、后代币B侧，
+/// 每组依次为 [Mint, 第一个代币账户, 第二个代币账户]，其后可追加TransferHook所需账户
+pub const BASKET_LEG_ACCOUNTS: usize = 3;
+
+/// 一条额外交易腿在remaining_accounts中的账户组
+struct LegAccounts<'info> {
+    mint: InterfaceAccount<'info, Mint>,
+    first: &'info AccountInfo<'info>,
+    second: &'info AccountInfo<'info>,
+}
+
+/// 取出第`index`条腿的账户组，并校验Mint与代币程序
+fn leg_accounts<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    index: usize,
+    leg: &OfferLeg,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<LegAccounts<'info>> {
+    let group = remaining_accounts
+        .get(index * BASKET_LEG_ACCOUNTS..(index + 1) * BASKET_LEG_ACCOUNTS)
+        .ok_or(ErrorCode::MissingBasketAccounts)?;
+
+    require_keys_eq!(*group[0].key, leg.mint, ErrorCode::WrongTokenMint);
+    require_keys_eq!(
+        *group[0].owner,
+        token_program.key(),
+        ErrorCode::WrongTokenProgram
+    );
+
+    Ok(LegAccounts {
+        mint: InterfaceAccount::try_from(&group[0])?,
+        first: &group[1],
+        second: &group[2],
+    })
+}
+
+/// 校验（必要时创建）`owner`持有的关联代币账户，并反序列化返回
+pub fn associated_token_account<'info>(
+    account: &'info AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    payer: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<InterfaceAccount<'info, TokenAccount>> {
+    require_keys_eq!(
+        *account.key,
+        get_associated_token_address_with_program_id(owner.key, &mint.key(), token_program.key),
+        ErrorCode::WrongTokenAccount
+    );
+
+    create_idempotent(CpiContext::new(
+        associated_token_program.to_account_info(),
+        Create {
+            payer: payer.clone(),
+            associated_token: account.clone(),
+            authority: owner.clone(),
+            mint: mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        },
+    ))?;
+
+    InterfaceAccount::try_from(account)
+}
+
+/// 校验一篮子报价的额外交易腿
+///
+/// 每侧不超过上限、数量大于零，且所有腿（含主交易对）的Mint互不相同
+pub fn validate_basket_legs(
+    token_mint_a: &Pubkey,
+    token_mint_b: &Pubkey,
+    extra_legs_a: &[OfferLeg],
+    extra_legs_b: &[OfferLeg],
+) -> Result<()> {
+    require!(
+        extra_legs_a.len() <= MAX_BASKET_LEGS && extra_legs_b.len() <= MAX_BASKET_LEGS,
+        ErrorCode::TooManyBasketLegs
+    );
+
+    for (legs, primary_mint) in [(extra_legs_a, token_mint_a), (extra_legs_b, token_mint_b)] {
+        for (index, leg) in legs.iter().enumerate() {
+            require!(leg.amount > 0, ErrorCode::InvalidAmount);
+            require!(
+                leg.mint != *primary_mint
+                    && legs[..index].iter().all(|other| other.mint != leg.mint),
+                ErrorCode::DuplicateBasketMint
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// 将代币A侧每条额外腿的代币存入对应的托管账户
+///
+/// 托管账户为报价PDA持有的关联代币账户，按需创建；
+/// 存入后以托管账户实际到账的数量更新腿的数量；每条腿的代币都须通过全局配置的代币检查，
+/// 额外腿不收取创作者版税，因此不能是NFT
+///
+/// 每组账户为 [Mint, 创建者代币账户, 托管账户]
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_basket_legs<'info>(
+    maker: &Signer<'info>,
+    offer: &AccountInfo<'info>,
+    config: &SwapConfig,
+    extra_legs_a: &mut [OfferLeg],
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    for (index, leg) in extra_legs_a.iter_mut().enumerate() {
+        let accounts = leg_accounts(remaining_accounts, index, leg, token_program)?;
+        check_mint(config, &accounts.mint)?;
+        require!(!is_nft(&accounts.mint), ErrorCode::NftBasketLeg);
+
+        let maker_token_account: InterfaceAccount<TokenAccount> =
+            InterfaceAccount::try_from(accounts.first)?;
+        let vault = associated_token_account(
+            accounts.second,
+            offer,
+            &accounts.mint,
+            &maker.to_account_info(),
+            token_program,
+            associated_token_program,
+            system_program,
+        )?;
+
+        transfer_tokens(
+            &maker_token_account,
+            &vault,
+            &leg.amount,
+            &accounts.mint,
+            &maker.to_account_info(),
+            token_program,
+            &[],
+            remaining_accounts,
+        )?;
+
+        leg.amount = token_account_balance(accounts.second)?;
+        require!(leg.amount > 0, ErrorCode::InvalidAmount);
+    }
+
+    Ok(())
+}
+
+/// 结算一篮子报价的全部额外交易腿
+///
+/// 代币A侧：将托管账户余额转给接受者并关闭托管账户，账户组为 [Mint, 托管账户, 接受者代币账户]；
+/// 代币B侧：接受者向创建者足额支付，账户组为 [Mint, 接受者代币账户, 创建者代币账户]；
+/// 每条腿的代币都须仍然通过全局配置的代币检查
+#[allow(clippy::too_many_arguments)]
+pub fn settle_basket_legs<'info>(
+    taker: &Signer<'info>,
+    maker: &AccountInfo<'info>,
+    offer: &AccountInfo<'info>,
+    config: &SwapConfig,
+    signer_seeds: &[&[&[u8]]],
+    extra_legs_a: &[OfferLeg],
+    extra_legs_b: &[OfferLeg],
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_program_a: &Interface<'info, TokenInterface>,
+    token_program_b: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    for (index, leg) in extra_legs_a.iter().enumerate() {
+        let accounts = leg_accounts(remaining_accounts, index, leg, token_program_a)?;
+        check_mint(config, &accounts.mint)?;
+
+        let vault = vault_account(accounts.first, offer, &accounts.mint, token_program_a)?;
+        let taker_token_account = associated_token_account(
+            accounts.second,
+            &taker.to_account_info(),
+            &accounts.mint,
+            &taker.to_account_info(),
+            token_program_a,
+            associated_token_program,
+            system_program,
+        )?;
+
+        withdraw_and_close_leg_vault(
+            &vault,
+            &taker_token_account,
+            &accounts.mint,
+            offer,
+            &taker.to_account_info(),
+            token_program_a,
+            signer_seeds,
+            remaining_accounts,
+        )?;
+    }
+
+    for (index, leg) in extra_legs_b.iter().enumerate() {
+        let accounts = leg_accounts(
+            remaining_accounts,
+            extra_legs_a.len() + index,
+            leg,
+            token_program_b,
+        )?;
+        check_mint(config, &accounts.mint)?;
+
+        let taker_token_account: InterfaceAccount<TokenAccount> =
+            InterfaceAccount::try_from(accounts.first)?;
+        let maker_token_account = associated_token_account(
+            accounts.second,
+            maker,
+            &accounts.mint,
+            &taker.to_account_info(),
+            token_program_b,
+            associated_token_program,
+            system_program,
+        )?;
+
+        transfer_tokens_exact(
+            &taker_token_account,
+            &maker_token_account,
+            &leg.amount,
+            &accounts.mint,
+            &taker.to_account_info(),
+            token_program_b,
+            &[],
+            remaining_accounts,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 将代币A侧每条额外腿的托管代币退还给创建者，并关闭托管账户
+///
+/// 每组账户为 [Mint, 托管账户, 创建者代币账户]，托管账户租金返还给创建者
+#[allow(clippy::too_many_arguments)]
+pub fn refund_basket_legs<'info>(
+    payer: &AccountInfo<'info>,
+    maker: &AccountInfo<'info>,
+    offer: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    extra_legs_a: &[OfferLeg],
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    for (index, leg) in extra_legs_a.iter().enumerate() {
+        let accounts = leg_accounts(remaining_accounts, index, leg, token_program)?;
+
+        let vault = vault_account(accounts.first, offer, &accounts.mint, token_program)?;
+        let maker_token_account = associated_token_account(
+            accounts.second,
+            maker,
+            &accounts.mint,
+            payer,
+            token_program,
+            associated_token_program,
+            system_program,
+        )?;
+
+        withdraw_and_close_leg_vault(
+            &vault,
+            &maker_token_account,
+            &accounts.mint,
+            offer,
+            maker,
+            token_program,
+            signer_seeds,
+            remaining_accounts,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 校验并反序列化报价PDA持有的托管账户
+pub fn vault_account<'info>(
+    account: &'info AccountInfo<'info>,
+    offer: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<InterfaceAccount<'info, TokenAccount>> {
+    require_keys_eq!(
+        *account.key,
+        get_associated_token_address_with_program_id(offer.key, &mint.key(), token_program.key),
+        ErrorCode::WrongTokenAccount
+    );
+    InterfaceAccount::try_from(account)
+}
+
+/// 提取托管账户全部余额并关闭托管账户
+#[allow(clippy::too_many_arguments)]
+fn withdraw_and_close_leg_vault<'info>(
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    offer: &AccountInfo<'info>,
+    rent_destination: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer_seeds: &[&[&[u8]]],
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    transfer_tokens(
+        vault,
+        to,
+        &vault.amount,
+        mint,
+        offer,
+        token_program,
+        signer_seeds,
+        remaining_accounts,
+    )?;
+
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault.to_account_info(),
+            destination: rent_destination.clone(),
+            authority: offer.clone(),
+        },
+        signer_seeds,
+    ))
+}
@@ -1,17 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
-    },
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
 };
 
-use crate::Offer;
+use crate::{error::ErrorCode, MakerProfile, Offer, OfferCancelled};
+
+use super::{
+    escrowed_lamports, is_native_mint, refund_basket_legs, transfer_lamports_from_pda,
+    transfer_tokens,
+};
 
 /// 撤销报价的账户结构
 ///
 /// 允许报价创建者撤销未成交的报价，并取回托管的代币
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(offer_id: u64)]
 pub struct CancelOffer<'info> {
@@ -20,40 +23,49 @@ pub struct CancelOffer<'info> {
     pub maker: Signer<'info>,
 
     /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
     pub token_mint_a: InterfaceAccount<'info, Mint>,
 
-    /// 报价创建者的代币A账户（接收退回的代币）
+    /// 报价创建者的代币A账户（接收退回的代币，代币A为原生SOL时不传）
     #[account(
         mut,
         associated_token::mint = token_mint_a,
         associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub maker_token_account_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     /// 报价状态账户
     #[account(
         mut,
-        has_one = maker @ crate::error::ErrorCode::NotMaker,
-        has_one = token_mint_a @ crate::error::ErrorCode::WrongTokenMint,
+        has_one = maker @ ErrorCode::NotMaker,
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
         seeds = [b"offer", maker.key().as_ref(), offer_id.to_le_bytes().as_ref()],
         bump = offer.bump,
     )]
     pub offer: Account<'info, Offer>,
 
-    /// 代币A的托管账户
+    /// 报价创建者档案账户（更新未关闭的报价数量）
+    #[account(
+        mut,
+        seeds = [b"maker_profile", maker.key().as_ref()],
+        bump = maker_profile.bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
+
+    /// 代币A的托管账户（代币A为原生SOL时不传）
     #[account(
         mut,
         associated_token::mint = token_mint_a,
         associated_token::authority = offer,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
     /// 系统程序
     pub system_program: Program<'info, System>,
-    /// 代币程序接口
-    pub token_program: Interface<'info, TokenInterface>,
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
     /// 关联代币程序
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -67,19 +79,50 @@ pub struct CancelOffer<'info> {
 ///
 /// # 返回值
 /// * `Result<()>` - 成功返回Ok，失败返回错误信息
-pub fn cancel_offer(context: Context<CancelOffer>) -> Result<()> {
+pub fn cancel_offer<'info>(
+    context: Context<'_, '_, 'info, 'info, CancelOffer<'info>>,
+) -> Result<()> {
     // 检查报价是否已经被取消
-    require!(!context.accounts.offer.is_cancelled, crate::error::ErrorCode::OfferAlreadyCancelled);
-    
-    // 将托管的代币转回给创建者
-    refund_to_maker(&context)?;
+    require!(
+        !context.accounts.offer.is_cancelled,
+        ErrorCode::OfferAlreadyCancelled
+    );
+    // 已成交的解锁报价中托管的代币A归属于接受者
+    require!(
+        !context.accounts.offer.is_vesting(),
+        ErrorCode::OfferIsVesting
+    );
+    // 仲裁报价的托管代币只能由仲裁人或双方共同处置
+    require!(
+        !context.accounts.offer.is_arbitrated(),
+        ErrorCode::OfferIsArbitrated
+    );
+
+    if is_native_mint(&context.accounts.token_mint_a.key()) {
+        // 原生SOL托管在报价账户中，直接退还超出租金的lamports
+        transfer_lamports_from_pda(
+            &context.accounts.offer.to_account_info(),
+            &context.accounts.maker.to_account_info(),
+            escrowed_lamports(&context.accounts.offer.to_account_info())?,
+        )?;
+    } else {
+        // 将托管的代币转回给创建者
+        refund_to_maker(&context)?;
+
+        // 关闭托管账户并将租金返还给创建者
+        close_vault_transfer_to_maker(&context)?;
+    }
+
+    // 退还一篮子报价额外托管的代币
+    refund_basket_to_maker(&context)?;
 
-    // 关闭托管账户并将租金返还给创建者
-    close_vault_transfer_to_maker(&context)?;
+    let ctx = &context;
+    emit_cpi!(OfferCancelled::new(&ctx.accounts.offer));
 
     // 标记报价为已取消
     let offer = &mut context.accounts.offer;
     offer.is_cancelled = true;
+    context.accounts.maker_profile.close_offer();
 
     Ok(())
 }
@@ -91,32 +134,30 @@ pub fn cancel_offer(context: Context<CancelOffer>) -> Result<()> {
 ///
 /// # 返回值
 /// * `Result<()>` - 操作结果
-fn refund_to_maker(context: &Context<CancelOffer>) -> Result<()> {
-    let seeds = &[
-        b"offer",
-        context.accounts.maker.to_account_info().key.as_ref(),
-        &context.accounts.offer.offer_id.to_le_bytes()[..],
-        &[context.accounts.offer.bump],
-    ];
-    let signer_seeds = [&seeds[..]];
-
-    let accounts = TransferChecked {
-        from: context.accounts.vault.to_account_info(),
-        to: context.accounts.maker_token_account_a.to_account_info(),
-        mint: context.accounts.token_mint_a.to_account_info(),
-        authority: context.accounts.offer.to_account_info(),
-    };
-
-    let cpi_context = CpiContext::new_with_signer(
-        context.accounts.token_program.to_account_info(),
-        accounts,
+fn refund_to_maker<'info>(
+    context: &Context<'_, '_, 'info, 'info, CancelOffer<'info>>,
+) -> Result<()> {
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    let vault = context
+        .accounts
+        .vault
+        .as_ref()
+        .ok_or(ErrorCode::MissingTokenAccount)?;
+    transfer_tokens(
+        vault,
+        context
+            .accounts
+            .maker_token_account_a
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
+        &vault.amount,
+        &context.accounts.token_mint_a,
+        &context.accounts.offer.to_account_info(),
+        &context.accounts.token_program_a,
         &signer_seeds,
-    );
-
-    transfer_checked(
-        cpi_context,
-        context.accounts.vault.amount,
-        context.accounts.token_mint_a.decimals,
+        context.remaining_accounts,
     )
 }
 
@@ -128,25 +169,53 @@ fn refund_to_maker(context: &Context<CancelOffer>) -> Result<()> {
 /// # 返回值
 /// * `Result<()>` - 操作结果
 fn close_vault_transfer_to_maker(context: &Context<CancelOffer>) -> Result<()> {
-    let seeds = &[
-        b"offer",
-        context.accounts.maker.to_account_info().key.as_ref(),
-        &context.accounts.offer.offer_id.to_le_bytes()[..],
-        &[context.accounts.offer.bump],
-    ];
-    let signer_seeds = [&seeds[..]];
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
 
     let accounts = CloseAccount {
-        account: context.accounts.vault.to_account_info(),
+        account: context
+            .accounts
+            .vault
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?
+            .to_account_info(),
         destination: context.accounts.maker.to_account_info(),
         authority: context.accounts.offer.to_account_info(),
     };
 
     let cpi_context = CpiContext::new_with_signer(
-        context.accounts.token_program.to_account_info(),
+        context.accounts.token_program_a.to_account_info(),
         accounts,
         &signer_seeds,
     );
 
     close_account(cpi_context)
 }
+
+/// 退还一篮子报价代币A侧额外托管的代币，并关闭对应的托管账户
+///
+/// remaining_accounts最前面为每条额外腿的 [Mint, 托管账户, 创建者代币账户]
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn refund_basket_to_maker<'info>(
+    context: &Context<'_, '_, 'info, 'info, CancelOffer<'info>>,
+) -> Result<()> {
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    refund_basket_legs(
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.offer.to_account_info(),
+        &signer_seeds,
+        &context.accounts.offer.extra_legs_a,
+        context.remaining_accounts,
+        &context.accounts.token_program_a,
+        &context.accounts.associated_token_program,
+        &context.accounts.system_program,
+    )
+}
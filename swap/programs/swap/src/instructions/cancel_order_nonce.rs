@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, OrderNonce, ANCHOR_DISCRIMINATOR};
+
+/// 创建者取消链下签名订单的账户结构
+#[derive(Accounts)]
+#[instruction(order_nonce: u64)]
+pub struct CancelOrderNonce<'info> {
+    /// 订单创建者（必须是签名者，支付随机数账户租金）
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// 订单随机数账户，提前创建后使用该随机数的订单都无法成交
+    #[account(
+        init,
+        payer = maker,
+        space = ANCHOR_DISCRIMINATOR + OrderNonce::INIT_SPACE,
+        seeds = [b"nonce", maker.key().as_ref(), order_nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub nonce: Account<'info, OrderNonce>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+/// 在订单过期前取消使用指定随机数签名的订单
+///
+/// `expires_at`不得早于使用该随机数签名的订单中最晚的过期时间，
+/// 否则随机数账户关闭后尚未过期的订单可以再次成交
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `_order_nonce` - 要取消的订单随机数（用于推导随机数账户地址）
+/// * `expires_at` - 订单过期的Unix时间戳
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn cancel_order_nonce(
+    context: Context<CancelOrderNonce>,
+    _order_nonce: u64,
+    expires_at: i64,
+) -> Result<()> {
+    require!(
+        Clock::get()?.unix_timestamp < expires_at,
+        ErrorCode::OfferExpired
+    );
+
+    context.accounts.nonce.set_inner(OrderNonce {
+        payer: context.accounts.maker.key(),
+        expires_at,
+        bump: context.bumps.nonce,
+    });
+    Ok(())
+}
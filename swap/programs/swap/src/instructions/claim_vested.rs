@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, Offer, VestedTokensClaimed};
+
+use super::transfer_tokens;
+
+/// 提取已解锁代币A的账户结构
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    /// 成交该报价的接受者（必须是签名者）
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    /// 报价创建者账户（全部提取后接收报价账户的租金）
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+    /// 接受者代币A的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program_a
+    )]
+    pub beneficiary_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 报价状态账户
+    #[account(
+        mut,
+        has_one = maker @ ErrorCode::NotMaker,
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = offer.vesting_beneficiary == Some(beneficiary.key())
+            @ ErrorCode::NotVestingBeneficiary,
+        seeds = [b"offer", maker.key().as_ref(), offer.offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 代币A的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 提取已解锁的代币A
+///
+/// 将当前已解锁但尚未提取的代币A转给接受者；全部解锁后的最后一次提取转出托管账户
+/// 全部余额，关闭托管账户（租金返还给接受者）和报价账户（租金返还给创建者）；
+/// 每次提取都发出提取事件
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn claim_vested<'info>(
+    context: Context<'_, '_, 'info, 'info, ClaimVested<'info>>,
+) -> Result<()> {
+    let offer = &context.accounts.offer;
+    let claimable_amount = offer.claimable_token_a_amount(Clock::get()?.unix_timestamp)?;
+    require!(claimable_amount > 0, ErrorCode::NothingToClaim);
+
+    let claimed_amount = offer
+        .token_a_claimed_amount
+        .checked_add(claimable_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let is_final_claim = claimed_amount == offer.token_a_vesting_amount;
+    let transfer_amount = if is_final_claim {
+        context.accounts.vault.amount
+    } else {
+        claimable_amount
+    };
+
+    let seeds = offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.beneficiary_token_account_a,
+        &transfer_amount,
+        &context.accounts.token_mint_a,
+        &offer.to_account_info(),
+        &context.accounts.token_program_a,
+        &signer_seeds,
+        context.remaining_accounts,
+    )?;
+
+    let ctx = &context;
+    emit_cpi!(VestedTokensClaimed {
+        offer_id: offer.offer_id,
+        maker: offer.maker,
+        beneficiary: ctx.accounts.beneficiary.key(),
+        token_mint_a: offer.token_mint_a,
+        token_a_amount: transfer_amount,
+        token_a_claimed_amount: claimed_amount,
+    });
+
+    if !is_final_claim {
+        context.accounts.offer.token_a_claimed_amount = claimed_amount;
+        return Ok(());
+    }
+
+    close_account(CpiContext::new_with_signer(
+        context.accounts.token_program_a.to_account_info(),
+        CloseAccount {
+            account: context.accounts.vault.to_account_info(),
+            destination: context.accounts.beneficiary.to_account_info(),
+            authority: offer.to_account_info(),
+        },
+        &signer_seeds,
+    ))?;
+    context
+        .accounts
+        .offer
+        .close(context.accounts.maker.to_account_info())
+}
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, OrderNonce};
+
+/// 关闭订单随机数账户的账户结构
+#[derive(Accounts)]
+pub struct CloseOrderNonce<'info> {
+    /// 当初支付随机数账户租金的账户（必须是签名者，接收返还的租金）
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 订单随机数账户（关闭后租金返还给支付者）
+    #[account(mut, close = payer, has_one = payer)]
+    pub nonce: Account<'info, OrderNonce>,
+}
+
+/// 订单过期后关闭随机数账户，返还租金
+///
+/// 过期的订单不能再成交，随机数账户不再需要用于防止重放
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn close_order_nonce(context: Context<CloseOrderNonce>) -> Result<()> {
+    require!(
+        Clock::get()?.unix_timestamp >= context.accounts.nonce.expires_at,
+        ErrorCode::OrderNotExpired
+    );
+    Ok(())
+}
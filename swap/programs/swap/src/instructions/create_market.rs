@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{Market, ANCHOR_DISCRIMINATOR};
+
+/// 创建交易对市场的账户结构
+///
+/// 任何人都可以为一个(代币A, 代币B)交易对创建市场，每个交易对方向只有一个市场
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    /// 创建者（支付市场账户租金）
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 代币A的Mint账户（报价出售的代币）
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户（报价想要的代币）
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 交易对市场账户
+    #[account(
+        init,
+        payer = payer,
+        space = ANCHOR_DISCRIMINATOR + Market::INIT_SPACE,
+        seeds = [b"market", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+/// 创建交易对市场
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn create_market(context: Context<CreateMarket>) -> Result<()> {
+    context.accounts.market.set_inner(Market {
+        token_mint_a: context.accounts.token_mint_a.key(),
+        token_mint_b: context.accounts.token_mint_b.key(),
+        offers: Vec::new(),
+        bump: context.bumps.market,
+    });
+    Ok(())
+}
@@ -0,0 +1,328 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        ed25519_program,
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, FeeSide, OrderNonce, SignedOrder, SwapConfig, ANCHOR_DISCRIMINATOR};
+
+use super::{check_mint, is_nft, transfer_tokens_exact};
+
+/// Ed25519程序指令数据中第一组签名偏移量的起始位置（签名数量1字节 + 填充1字节）
+const ED25519_OFFSETS_START: usize = 2;
+/// Ed25519程序指令数据中一组签名偏移量的长度（7个u16）
+const ED25519_OFFSETS_SIZE: usize = 14;
+
+/// 成交链下签名订单的账户结构
+///
+/// 交易中紧邻本指令之前必须是验证创建者签名的Ed25519程序指令；
+/// 创建者需预先将代币A账户的额度授权给以[b"delegate", 创建者]为种子的PDA，
+/// 代币A带有转账手续费时授权额度需覆盖转账手续费
+#[derive(Accounts)]
+#[instruction(order: SignedOrder)]
+pub struct FillSignedOrder<'info> {
+    /// 交易执行者，需提供签名
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// 订单创建者账户
+    #[account(mut, address = order.maker @ ErrorCode::NotMaker)]
+    pub maker: SystemAccount<'info>,
+
+    /// 代币A的Mint账户
+    #[account(
+        address = order.token_mint_a @ ErrorCode::WrongTokenMint,
+        mint::token_program = token_program_a
+    )]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    #[account(
+        address = order.token_mint_b @ ErrorCode::WrongTokenMint,
+        mint::token_program = token_program_b
+    )]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 订单创建者的代币A账户（已将额度授权给delegate）
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 订单创建者代币B的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 交易执行者代币A的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 交易执行者代币B的关联账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 创建者代币A账户的授权代理PDA
+    /// CHECK: 仅作为转账授权的PDA签名者，不读写数据
+    #[account(seeds = [b"delegate", maker.key().as_ref()], bump)]
+    pub delegate: UncheckedAccount<'info>,
+
+    /// 订单随机数账户，创建成功即标记该订单已成交，防止重放；
+    /// 创建者已取消该随机数时账户已存在，成交失败
+    #[account(
+        init,
+        payer = taker,
+        space = ANCHOR_DISCRIMINATOR + OrderNonce::INIT_SPACE,
+        seeds = [b"nonce", maker.key().as_ref(), order.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub nonce: Account<'info, OrderNonce>,
+
+    /// 全局配置账户（协议暂停时不能成交）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 手续费接收者的代币账户（代币A或代币B，取决于配置的收费方向；
+    /// 不收取手续费时可不传）
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_recipient @ ErrorCode::WrongFeeAccount,
+        constraint = fee_token_account.mint
+            == config.fee_mint(&token_mint_a.key(), &token_mint_b.key()) @ ErrorCode::WrongFeeAccount
+    )]
+    pub fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 指令系统变量账户，用于读取Ed25519签名验证指令
+    /// CHECK: 地址固定为指令系统变量
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// 系统程序账户
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序账户
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序账户
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序账户
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 成交链下签名订单主函数
+///
+/// 验证订单未过期且带有创建者的有效签名后，接受者向创建者支付代币B，
+/// 程序以授权代理PDA的身份从创建者的代币A账户向接受者划转代币A
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `order` - 创建者签名的订单
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn fill_signed_order<'info>(
+    context: Context<'_, '_, 'info, 'info, FillSignedOrder<'info>>,
+    order: SignedOrder,
+) -> Result<()> {
+    require!(
+        order.token_a_amount > 0 && order.token_b_amount > 0,
+        ErrorCode::InvalidAmount
+    );
+    require!(
+        !is_nft(&context.accounts.token_mint_a),
+        ErrorCode::NftOfferNotRoutable
+    );
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+    require!(
+        Clock::get()?.unix_timestamp < order.expires_at,
+        ErrorCode::OfferExpired
+    );
+    verify_maker_signature(&context.accounts.instructions, &order)?;
+
+    context.accounts.nonce.set_inner(OrderNonce {
+        payer: context.accounts.taker.key(),
+        expires_at: order.expires_at,
+        bump: context.bumps.nonce,
+    });
+
+    send_wanted_tokens_to_maker(&context, order.token_b_amount)?;
+    send_offered_tokens_to_taker(&context, order.token_a_amount)
+}
+
+/// 检查紧邻本指令之前的Ed25519程序指令验证了创建者对订单的签名
+///
+/// 签名、公钥和消息都必须位于该Ed25519指令自身的数据中，
+/// 且公钥为订单创建者、消息为`SignedOrder::message`
+fn verify_maker_signature(instructions: &AccountInfo, order: &SignedOrder) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::InvalidOrderSignature);
+
+    let instruction = load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+    require!(
+        instruction.program_id == ed25519_program::ID && instruction.accounts.is_empty(),
+        ErrorCode::InvalidOrderSignature
+    );
+
+    let data = &instruction.data;
+    require!(
+        data.len() >= ED25519_OFFSETS_START + ED25519_OFFSETS_SIZE && data[0] == 1,
+        ErrorCode::InvalidOrderSignature
+    );
+    let offsets = &data[ED25519_OFFSETS_START..ED25519_OFFSETS_START + ED25519_OFFSETS_SIZE];
+    let read_offset = |index: usize| u16::from_le_bytes([offsets[index], offsets[index + 1]]);
+
+    let signature_instruction_index = read_offset(2);
+    let public_key_offset = read_offset(4) as usize;
+    let public_key_instruction_index = read_offset(6);
+    let message_offset = read_offset(8) as usize;
+    let message_size = read_offset(10) as usize;
+    let message_instruction_index = read_offset(12);
+
+    require!(
+        signature_instruction_index == u16::MAX
+            && public_key_instruction_index == u16::MAX
+            && message_instruction_index == u16::MAX,
+        ErrorCode::InvalidOrderSignature
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ErrorCode::InvalidOrderSignature)?;
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidOrderSignature)?;
+    require!(
+        public_key == order.maker.as_ref() && message == order.message()?.as_slice(),
+        ErrorCode::InvalidOrderSignature
+    );
+
+    Ok(())
+}
+
+/// 接受者向创建者支付代币B，手续费从代币B收取时先转入手续费账户
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `token_b_amount` - 订单想要的代币B数量
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn send_wanted_tokens_to_maker<'info>(
+    context: &Context<'_, '_, 'info, 'info, FillSignedOrder<'info>>,
+    token_b_amount: u64,
+) -> Result<()> {
+    let fee = match context.accounts.config.fee_side {
+        FeeSide::TokenB => context.accounts.config.protocol_fee(token_b_amount)?,
+        FeeSide::TokenA => 0,
+    };
+
+    if fee > 0 {
+        transfer_tokens_exact(
+            &context.accounts.taker_token_account_b,
+            context
+                .accounts
+                .fee_token_account
+                .as_deref()
+                .ok_or(ErrorCode::WrongFeeAccount)?,
+            &fee,
+            &context.accounts.token_mint_b,
+            &context.accounts.taker.to_account_info(),
+            &context.accounts.token_program_b,
+            &[],
+            context.remaining_accounts,
+        )?;
+    }
+
+    transfer_tokens_exact(
+        &context.accounts.taker_token_account_b,
+        &context.accounts.maker_token_account_b,
+        &(token_b_amount - fee),
+        &context.accounts.token_mint_b,
+        &context.accounts.taker.to_account_info(),
+        &context.accounts.token_program_b,
+        &[],
+        context.remaining_accounts,
+    )
+}
+
+/// 以授权代理PDA的身份从创建者的代币A账户向接受者划转代币A，
+/// 手续费从代币A收取时先转入手续费账户。
+/// 代币A带有转账手续费时由创建者额外承担，保证接受者按订单数量足额到账
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `token_a_amount` - 订单提供的代币A数量
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn send_offered_tokens_to_taker<'info>(
+    context: &Context<'_, '_, 'info, 'info, FillSignedOrder<'info>>,
+    token_a_amount: u64,
+) -> Result<()> {
+    let fee = match context.accounts.config.fee_side {
+        FeeSide::TokenA => context.accounts.config.protocol_fee(token_a_amount)?,
+        FeeSide::TokenB => 0,
+    };
+
+    let seeds = &[
+        b"delegate",
+        context.accounts.maker.to_account_info().key.as_ref(),
+        &[context.bumps.delegate],
+    ];
+    let signer_seeds = [&seeds[..]];
+
+    if fee > 0 {
+        transfer_tokens_exact(
+            &context.accounts.maker_token_account_a,
+            context
+                .accounts
+                .fee_token_account
+                .as_deref()
+                .ok_or(ErrorCode::WrongFeeAccount)?,
+            &fee,
+            &context.accounts.token_mint_a,
+            &context.accounts.delegate.to_account_info(),
+            &context.accounts.token_program_a,
+            &signer_seeds,
+            context.remaining_accounts,
+        )?;
+    }
+
+    transfer_tokens_exact(
+        &context.accounts.maker_token_account_a,
+        &context.accounts.taker_token_account_a,
+        &(token_a_amount - fee),
+        &context.accounts.token_mint_a,
+        &context.accounts.delegate.to_account_info(),
+        &context.accounts.token_program_a,
+        &signer_seeds,
+        context.remaining_accounts,
+    )
+}
@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    error::ErrorCode, Pool, SwapConfig, ANCHOR_DISCRIMINATOR, MAX_FEE_BPS, POOL_LP_DECIMALS,
+};
+
+use super::check_mint;
+
+/// 创建恒定乘积流动性池的账户结构
+///
+/// 任何人都可以为一个交易对创建池子，代币A地址必须小于代币B地址，
+/// LP代币的Mint和两个托管账户随池子一并创建
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    /// 创建者（支付池子相关账户的租金）
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 全局配置账户（协议暂停时不能创建池子）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+    /// 代币B的Mint账户（地址必须大于代币A）
+    #[account(
+        mint::token_program = token_program_b,
+        constraint = token_mint_a.key() < token_mint_b.key() @ ErrorCode::InvalidPoolMints
+    )]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 流动性池账户
+    #[account(
+        init,
+        payer = payer,
+        space = ANCHOR_DISCRIMINATOR + Pool::INIT_SPACE,
+        seeds = [b"pool", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// LP代币的Mint账户（由池子管理，使用代币A的代币程序）
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"pool_lp", pool.key().as_ref()],
+        bump,
+        mint::decimals = POOL_LP_DECIMALS,
+        mint::authority = pool,
+        mint::token_program = token_program_a
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// 代币A的托管账户
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 代币B的托管账户
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序接口
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 创建恒定乘积流动性池
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `fee_bps` - 兑换手续费（基点），留在池中归LP所有
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn init_pool(context: Context<InitPool>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeeBps);
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+
+    context.accounts.pool.set_inner(Pool {
+        token_mint_a: context.accounts.token_mint_a.key(),
+        token_mint_b: context.accounts.token_mint_b.key(),
+        lp_mint: context.accounts.lp_mint.key(),
+        fee_bps,
+        bump: context.bumps.pool,
+    });
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable};
+
+use crate::{
+    error::ErrorCode, FeeSide, MintListMode, SwapConfig, ANCHOR_DISCRIMINATOR, MAX_FEE_BPS,
+};
+
+/// 初始化全局配置的账户结构
+///
+/// 配置账户为全局唯一的PDA，只有程序的升级权限才能初始化并成为管理员，
+/// 防止部署后被他人抢先初始化
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// 管理员账户（签名者，支付配置账户租金），必须是程序的升级权限
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// 本程序的程序数据账户，记录程序的升级权限
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        seeds::program = bpf_loader_upgradeable::ID,
+        bump,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// 全局配置账户
+    #[account(
+        init,
+        payer = admin,
+        space = ANCHOR_DISCRIMINATOR + SwapConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+/// 初始化全局配置
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `fee_bps` - 协议手续费（基点）
+/// * `fee_recipient` - 手续费接收者
+/// * `fee_side` - 手续费的收取方向
+/// * `max_referral_bps` - 前端推荐费率上限（基点）
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn initialize_config(
+    context: Context<InitializeConfig>,
+    fee_bps: u16,
+    fee_recipient: Pubkey,
+    fee_side: FeeSide,
+    max_referral_bps: u16,
+) -> Result<()> {
+    require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeeBps);
+    require!(max_referral_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeeBps);
+
+    context.accounts.config.set_inner(SwapConfig {
+        admin: context.accounts.admin.key(),
+        fee_bps,
+        fee_recipient,
+        fee_side,
+        max_referral_bps,
+        paused: false,
+        mint_list_mode: MintListMode::Disabled,
+        mint_list: Vec::new(),
+        bump: context.bumps.config,
+    });
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Market, Offer};
+
+use super::is_native_mint;
+
+/// 将报价上架到交易对市场的账户结构
+#[derive(Accounts)]
+pub struct ListOffer<'info> {
+    /// 报价创建者（必须是签名者）
+    pub maker: Signer<'info>,
+
+    /// 报价状态账户
+    #[account(
+        has_one = maker @ ErrorCode::NotMaker,
+        seeds = [b"offer", maker.key().as_ref(), offer.offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 报价交易对所属的市场账户
+    #[account(
+        mut,
+        seeds = [b"market", offer.token_mint_a.as_ref(), offer.token_mint_b.as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// 将报价按当前价格上架到市场
+///
+/// 只有固定价格、立即交割、不限制接受者、不涉及原生SOL的普通报价可以上架；
+/// 报价修改时传入市场账户即可同步更新记录
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn list_offer(context: Context<ListOffer>) -> Result<()> {
+    let offer = &context.accounts.offer;
+    require!(!offer.is_cancelled, ErrorCode::OfferAlreadyCancelled);
+    require!(
+        !offer.is_expired(Clock::get()?.unix_timestamp),
+        ErrorCode::OfferExpired
+    );
+    require!(
+        !offer.is_basket()
+            && offer.dutch_auction.is_none()
+            && offer.vesting.is_none()
+            && !offer.is_arbitrated()
+            && offer.allowed_taker.is_none()
+            && offer.taker_merkle_root.is_none()
+            && !is_native_mint(&offer.token_mint_a)
+            && !is_native_mint(&offer.token_mint_b),
+        ErrorCode::OfferNotListable
+    );
+
+    context.accounts.market.insert(offer.key(), offer)
+}
@@ -4,16 +4,22 @@ use anchor_spl::{
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::{Offer, ANCHOR_DISCRIMINATOR};
+use crate::{
+    error::ErrorCode, DutchAuction, MakerProfile, Offer, OfferCreated, OfferLeg, SwapConfig,
+    VestingSchedule, ANCHOR_DISCRIMINATOR,
+};
 
-use super::transfer_tokens;
+use super::{
+    check_mint, deposit_basket_legs, escrowed_lamports, is_native_mint, is_nft, transfer_lamports,
+    transfer_tokens, validate_basket_legs,
+};
 
 /// 创建报价的账户结构
 /// 包含创建者、代币信息、资金库及系统程序等账户
 /// 使用Anchor框架的Accounts宏自动生成账户验证代码
+/// event_cpi宏追加记录事件所需的事件权限账户和程序账户
+#[event_cpi]
 #[derive(Accounts)]
-/// 指令参数包含报价ID
-#[instruction(id: u64)]
 pub struct MakeOffer<'info> {
     /// 交易发起人账户（签名者）
     /// mut表示该账户数据可变
@@ -21,90 +27,287 @@ pub struct MakeOffer<'info> {
     pub maker: Signer<'info>,
 
     /// 代币A的Mint账户
-    /// mint::token_program指定使用的代币程序
-    #[account(mint::token_program = token_program)]
+    /// mint::token_program校验该Mint属于代币A的代币程序
+    #[account(mint::token_program = token_program_a)]
     pub token_mint_a: InterfaceAccount<'info, Mint>,
 
     /// 代币B的Mint账户
-    /// 同样指定使用的代币程序
-    #[account(mint::token_program = token_program)]
+    /// 代币B可以使用与代币A不同的代币程序
+    #[account(mint::token_program = token_program_b)]
     pub token_mint_b: InterfaceAccount<'info, Mint>,
 
     /// 发起人的代币A账户
-    /// 关联到token_mint_a，由maker管理，使用指定的token_program
+    /// 关联到token_mint_a，由maker管理，使用代币A的token_program；
+    /// 代币A为原生SOL时不传
     #[account(
         mut,
         associated_token::mint = token_mint_a,
         associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_token_account_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 全局配置账户
+    /// 协议暂停时不能创建报价
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
     )]
-    pub maker_token_account_a: InterfaceAccount<'info, TokenAccount>,
+    pub config: Account<'info, SwapConfig>,
+
+    /// 创建者档案账户
+    /// 首次创建报价时初始化，由其计数器分配报价ID
+    #[account(
+        init_if_needed,
+        payer = maker,
+        space = ANCHOR_DISCRIMINATOR + MakerProfile::INIT_SPACE,
+        seeds = [b"maker_profile", maker.key().as_ref()],
+        bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
 
     /// 报价存储账户
     /// init表示该账户将被初始化
-    /// seeds指定PDA种子，bump表示自动处理nonce
+    /// seeds指定PDA种子，报价ID为创建者档案中的下一个ID，bump表示自动处理nonce
     #[account(
         init,
         payer = maker,
         space = ANCHOR_DISCRIMINATOR + Offer::INIT_SPACE,
-        seeds = [b"offer", maker.key().as_ref(), id.to_le_bytes().as_ref()],
+        seeds = [
+            b"offer",
+            maker.key().as_ref(),
+            maker_profile.next_offer_id.to_le_bytes().as_ref()
+        ],
         bump
     )]
     pub offer: Account<'info, Offer>,
 
     /// 资金库账户
-    /// 初始化为代币A的关联账户，由offer账户管理
+    /// 初始化为代币A的关联账户，由offer账户管理；
+    /// 代币A为原生SOL时不传，lamports直接托管在offer账户中
     #[account(
         init,
         payer = maker,
         associated_token::mint = token_mint_a,
         associated_token::authority = offer,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
     /// 系统程序账户
     pub system_program: Program<'info, System>,
-    
-    /// 代币程序接口
-    pub token_program: Interface<'info, TokenInterface>,
-    
+
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// 代币B的代币程序接口
+    pub token_program_b: Interface<'info, TokenInterface>,
+
     /// 关联代币程序
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+/// 按全局配置检查报价的代币A和代币B是否允许使用
+/// @param context 上下文包含所有必要账户
+/// @return Result<()> 操作结果
+pub fn check_offer_mints(context: &Context<MakeOffer>) -> Result<()> {
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)
+}
+
 /// 将报价代币转入资金库
+/// 代币A为原生SOL时，lamports直接转入offer账户托管
 /// @param context 上下文包含所有必要账户
 /// @param token_a_offered_amount 要转入的代币数量
 /// @return Result<()> 操作结果
-pub fn send_offered_tokens_to_vault(
-    context: &Context<MakeOffer>,
+pub fn send_offered_tokens_to_vault<'info>(
+    context: &Context<'_, '_, 'info, 'info, MakeOffer<'info>>,
     token_a_offered_amount: u64,
 ) -> Result<()> {
+    require!(token_a_offered_amount > 0, ErrorCode::InvalidAmount);
+
+    if is_native_mint(&context.accounts.token_mint_a.key()) {
+        return transfer_lamports(
+            &context.accounts.maker.to_account_info(),
+            &context.accounts.offer.to_account_info(),
+            token_a_offered_amount,
+            &context.accounts.system_program.to_account_info(),
+        );
+    }
+
     transfer_tokens(
-        &context.accounts.maker_token_account_a,
-        &context.accounts.vault,
+        context
+            .accounts
+            .maker_token_account_a
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
+        context
+            .accounts
+            .vault
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
         &token_a_offered_amount,
         &context.accounts.token_mint_a,
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.token_program_a,
+        &[],
+        context.remaining_accounts,
+    )
+}
+
+/// 校验一篮子报价的额外交易腿，并将代币A侧的额外代币转入各自的托管账户
+/// @param context 上下文，remaining_accounts最前面为代币A侧每条额外腿的 [Mint, 创建者代币账户, 托管账户]
+/// @param extra_legs_a 代币A侧的额外交易腿，存入后更新为实际托管数量
+/// @param extra_legs_b 代币B侧的额外交易腿
+/// @return Result<()> 操作结果
+pub fn send_basket_tokens_to_vaults<'info>(
+    context: &Context<'_, '_, 'info, 'info, MakeOffer<'info>>,
+    extra_legs_a: &mut [OfferLeg],
+    extra_legs_b: &[OfferLeg],
+) -> Result<()> {
+    validate_basket_legs(
+        &context.accounts.token_mint_a.key(),
+        &context.accounts.token_mint_b.key(),
+        extra_legs_a,
+        extra_legs_b,
+    )?;
+    // 代币B侧的Mint账户在成交时才传入，创建时先按代币名单检查，成交时再完整检查
+    for leg in extra_legs_b {
+        context.accounts.config.check_mint_list(&leg.mint)?;
+    }
+
+    deposit_basket_legs(
         &context.accounts.maker,
-        &context.accounts.token_program,
+        &context.accounts.offer.to_account_info(),
+        &context.accounts.config,
+        extra_legs_a,
+        context.remaining_accounts,
+        &context.accounts.token_program_a,
+        &context.accounts.associated_token_program,
+        &context.accounts.system_program,
     )
 }
 
-/// 保存报价信息到链上
-/// @param context 上下文包含offer账户
-/// @param id 报价ID
+/// 保存报价信息到链上，报价ID由创建者档案分配
+/// @param context 上下文包含offer账户和创建者档案账户
 /// @param token_b_wanted_amount 需要的代币B数量
+/// @param expires_at 报价过期时间戳，None表示永不过期
+/// @param allowed_taker 指定的唯一接受者
+/// @param taker_merkle_root 允许接受者名单的默克尔根
+/// @param extra_legs_a 代币A侧的额外交易腿（实际托管数量）
+/// @param extra_legs_b 代币B侧的额外交易腿
+/// @param dutch_auction 荷兰拍卖参数，None表示固定价格
+/// @param vesting 代币A的解锁计划，None表示成交时立即释放
+/// @param arbiter 仲裁人，设置后代币B在链下结算
+/// @param dispute_deadline 争议处理截止时间，仅仲裁报价需要
 /// @return Result<()> 操作结果
-pub fn save_offer(context: Context<MakeOffer>, id: u64, token_b_wanted_amount: u64) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn save_offer(
+    context: Context<MakeOffer>,
+    token_b_wanted_amount: u64,
+    expires_at: Option<i64>,
+    allowed_taker: Option<Pubkey>,
+    taker_merkle_root: Option<[u8; 32]>,
+    extra_legs_a: Vec<OfferLeg>,
+    extra_legs_b: Vec<OfferLeg>,
+    dutch_auction: Option<DutchAuction>,
+    vesting: Option<VestingSchedule>,
+    arbiter: Option<Pubkey>,
+    dispute_deadline: Option<i64>,
+) -> Result<()> {
+    require!(token_b_wanted_amount > 0, ErrorCode::InvalidAmount);
+    if let Some(dutch_auction) = dutch_auction {
+        dutch_auction.validate(token_b_wanted_amount)?;
+    }
+    if let Some(vesting) = vesting {
+        vesting.validate()?;
+        require!(
+            extra_legs_a.is_empty()
+                && extra_legs_b.is_empty()
+                && !is_native_mint(&context.accounts.token_mint_a.key()),
+            ErrorCode::InvalidVestingOffer
+        );
+    }
+    if let Some(expires_at) = expires_at {
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidExpiry
+        );
+    }
+    require!(
+        allowed_taker.is_none() || taker_merkle_root.is_none(),
+        ErrorCode::InvalidTakerRestriction
+    );
+    // 仲裁报价的代币B在链下与指定接受者结算，争议截止时间必须在未来；
+    // 放款时无法按代币B计算NFT版税，因此代币A不能是NFT
+    if arbiter.is_some() {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            allowed_taker.is_some()
+                && dispute_deadline.is_some_and(|deadline| deadline > now)
+                && extra_legs_a.is_empty()
+                && extra_legs_b.is_empty()
+                && dutch_auction.is_none()
+                && vesting.is_none()
+                && !is_native_mint(&context.accounts.token_mint_a.key())
+                && !is_nft(&context.accounts.token_mint_a),
+            ErrorCode::InvalidArbitratedOffer
+        );
+    } else {
+        require!(
+            dispute_deadline.is_none(),
+            ErrorCode::InvalidArbitratedOffer
+        );
+    }
+
+    // 以托管账户实际到账的数量为准，Token-2022转账手续费会使其少于转出数量；
+    // 原生SOL以offer账户超出租金的lamports为准
+    let token_a_offered_amount = if is_native_mint(&context.accounts.token_mint_a.key()) {
+        escrowed_lamports(&context.accounts.offer.to_account_info())?
+    } else {
+        let vault = context
+            .accounts
+            .vault
+            .as_mut()
+            .ok_or(ErrorCode::MissingTokenAccount)?;
+        vault.reload()?;
+        vault.amount
+    };
+    require!(token_a_offered_amount > 0, ErrorCode::InvalidAmount);
+
+    let maker_profile = &mut context.accounts.maker_profile;
+    maker_profile.maker = context.accounts.maker.key();
+    maker_profile.bump = context.bumps.maker_profile;
+    let offer_id = maker_profile.open_offer()?;
+
     context.accounts.offer.set_inner(Offer {
-        offer_id: (id),
+        offer_id: (offer_id),
         maker: (context.accounts.maker.key()),
         token_mint_a: (context.accounts.token_mint_a.key()),
         token_mint_b: (context.accounts.token_mint_b.key()),
         token_b_wanted_amount: (token_b_wanted_amount),
+        token_a_offered_amount: (token_a_offered_amount),
+        token_a_remaining_amount: (token_a_offered_amount),
+        token_b_remaining_amount: (token_b_wanted_amount),
+        expires_at: (expires_at),
+        allowed_taker: (allowed_taker),
+        taker_merkle_root: (taker_merkle_root),
+        extra_legs_a: (extra_legs_a),
+        extra_legs_b: (extra_legs_b),
+        dutch_auction: (dutch_auction),
+        revision: 0,
+        vesting: (vesting),
+        vesting_beneficiary: None,
+        token_a_vesting_amount: 0,
+        token_a_claimed_amount: 0,
+        arbiter: (arbiter),
+        dispute_deadline: (dispute_deadline),
         bump: (context.bumps.offer),
         is_cancelled: false,
     });
+
+    let ctx = &context;
+    emit_cpi!(OfferCreated::new(&ctx.accounts.offer));
     Ok(())
 }
@@ -1,9 +1,57 @@
+pub mod add_liquidity;
+pub mod basket;
 pub mod cancel_offer;
+pub mod cancel_order_nonce;
+pub mod claim_vested;
+pub mod close_order_nonce;
+pub mod create_market;
+pub mod fill_signed_order;
+pub mod init_pool;
+pub mod initialize_config;
+pub mod list_offer;
 pub mod make_offer;
+pub mod prune_market;
+pub mod reclaim_expired_offer;
+pub mod refund_to_maker;
+pub mod release_to_taker;
+pub mod remove_liquidity;
+pub mod royalty;
+pub mod settlement;
 pub mod shared;
+pub mod swap_exact_in;
+pub mod take_best;
 pub mod take_offer;
+pub mod take_offers;
+pub mod unlist_offer;
+pub mod update_config;
+pub mod update_mint_list;
+pub mod update_offer;
 
+pub use add_liquidity::*;
+pub use basket::*;
 pub use cancel_offer::*;
+pub use cancel_order_nonce::*;
+pub use claim_vested::*;
+pub use close_order_nonce::*;
+pub use create_market::*;
+pub use fill_signed_order::*;
+pub use init_pool::*;
+pub use initialize_config::*;
+pub use list_offer::*;
 pub use make_offer::*;
+pub use prune_market::*;
+pub use reclaim_expired_offer::*;
+pub use refund_to_maker::*;
+pub use release_to_taker::*;
+pub use remove_liquidity::*;
+pub use royalty::*;
+pub use settlement::*;
 pub use shared::*;
+pub use swap_exact_in::*;
+pub use take_best::*;
 pub use take_offer::*;
+pub use take_offers::*;
+pub use unlist_offer::*;
+pub use update_config::*;
+pub use update_mint_list::*;
+pub use update_offer::*;
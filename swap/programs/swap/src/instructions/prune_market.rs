@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Market, Offer};
+
+/// 清理交易对市场中失效报价的账户结构
+///
+/// 任何人都可以调用，remaining_accounts依次传入要检查的报价账户
+#[derive(Accounts)]
+pub struct PruneMarket<'info> {
+    /// 交易对市场账户
+    #[account(
+        mut,
+        seeds = [b"market", market.token_mint_a.as_ref(), market.token_mint_b.as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// 将已关闭、已取消、已过期或上架后被修改的报价从市场中移除
+///
+/// 传入的报价必须已在市场中上架；仍然有效的报价保留不变
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn prune_market<'info>(
+    context: Context<'_, '_, 'info, 'info, PruneMarket<'info>>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = &mut context.accounts.market;
+
+    let mut pruned = 0;
+    for account in context.remaining_accounts {
+        let index = market
+            .position(account.key)
+            .ok_or(ErrorCode::OfferNotListed)?;
+
+        // 报价已被全部成交或回收并关闭
+        let is_stale = if account.data_is_empty() {
+            true
+        } else {
+            let offer: Account<Offer> = Account::try_from(account)?;
+            offer.is_cancelled
+                || offer.is_expired(now)
+                || offer.revision != market.offers[index].revision
+        };
+        if is_stale {
+            market.offers.remove(index);
+            pruned += 1;
+        }
+    }
+
+    require!(pruned > 0, ErrorCode::NothingToPrune);
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, MakerProfile, Offer, OfferExpired};
+
+use super::{is_native_mint, refund_basket_legs, transfer_tokens};
+
+/// 回收过期报价的账户结构
+///
+/// 任何人都可以在报价过期后调用，将托管的代币退回给创建者，
+/// 并关闭托管账户和报价账户
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimExpiredOffer<'info> {
+    /// 触发回收的账户（如创建者的代币A账户不存在，由其支付创建费用）
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 报价创建者账户（接收代币及全部租金）
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+    /// 报价创建者的代币A账户（如果不存在则自动创建，代币A为原生SOL时不传）
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_token_account_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 报价状态账户（回收后关闭并将租金返还给创建者）
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        seeds = [b"offer", maker.key().as_ref(), offer.offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 报价创建者档案账户（更新未关闭的报价数量）
+    #[account(
+        mut,
+        seeds = [b"maker_profile", maker.key().as_ref()],
+        bump = maker_profile.bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
+
+    /// 代币A的托管账户（代币A为原生SOL时不传）
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 回收过期报价主函数
+///
+/// 验证报价已过期后，将托管的代币退回给创建者，并关闭托管账户
+///
+/// # 参数
+/// * `context` - 指令上下文，包含所有相关账户
+///
+/// # 返回值
+/// * `Result<()>` - 成功返回Ok，失败返回错误信息
+pub fn reclaim_expired_offer<'info>(
+    context: Context<'_, '_, 'info, 'info, ReclaimExpiredOffer<'info>>,
+) -> Result<()> {
+    require!(
+        !context.accounts.offer.is_cancelled,
+        ErrorCode::OfferAlreadyCancelled
+    );
+    // 已成交的解锁报价中托管的代币A归属于接受者
+    require!(
+        !context.accounts.offer.is_vesting(),
+        ErrorCode::OfferIsVesting
+    );
+    // 仲裁报价的托管代币只能由仲裁人或双方共同处置
+    require!(
+        !context.accounts.offer.is_arbitrated(),
+        ErrorCode::OfferIsArbitrated
+    );
+    require!(
+        context
+            .accounts
+            .offer
+            .is_expired(Clock::get()?.unix_timestamp),
+        ErrorCode::OfferNotExpired
+    );
+
+    // 原生SOL托管在报价账户中，随报价账户由close约束一并退还给创建者
+    if !is_native_mint(&context.accounts.token_mint_a.key()) {
+        // 将托管的代币退还给创建者
+        refund_expired_to_maker(&context)?;
+
+        // 关闭托管账户并将租金返还给创建者，报价账户由close约束关闭
+        close_expired_vault(&context)?;
+    }
+
+    context.accounts.maker_profile.close_offer();
+
+    let ctx = &context;
+    emit_cpi!(OfferExpired::new(&ctx.accounts.offer));
+
+    // 退还一篮子报价额外托管的代币
+    refund_expired_basket_to_maker(&context)
+}
+
+/// 将过期报价托管的代币退还给报价创建者
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn refund_expired_to_maker<'info>(
+    context: &Context<'_, '_, 'info, 'info, ReclaimExpiredOffer<'info>>,
+) -> Result<()> {
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    let vault = context
+        .accounts
+        .vault
+        .as_ref()
+        .ok_or(ErrorCode::MissingTokenAccount)?;
+    transfer_tokens(
+        vault,
+        context
+            .accounts
+            .maker_token_account_a
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
+        &vault.amount,
+        &context.accounts.token_mint_a,
+        &context.accounts.offer.to_account_info(),
+        &context.accounts.token_program_a,
+        &signer_seeds,
+        context.remaining_accounts,
+    )
+}
+
+/// 关闭过期报价的托管账户并将租金返还给报价创建者
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn close_expired_vault(context: &Context<ReclaimExpiredOffer>) -> Result<()> {
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    let accounts = CloseAccount {
+        account: context
+            .accounts
+            .vault
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?
+            .to_account_info(),
+        destination: context.accounts.maker.to_account_info(),
+        authority: context.accounts.offer.to_account_info(),
+    };
+
+    let cpi_context = CpiContext::new_with_signer(
+        context.accounts.token_program_a.to_account_info(),
+        accounts,
+        &signer_seeds,
+    );
+
+    close_account(cpi_context)
+}
+
+/// 退还过期一篮子报价代币A侧额外托管的代币，并关闭对应的托管账户
+///
+/// remaining_accounts最前面为每条额外腿的 [Mint, 托管账户, 创建者代币账户]
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn refund_expired_basket_to_maker<'info>(
+    context: &Context<'_, '_, 'info, 'info, ReclaimExpiredOffer<'info>>,
+) -> Result<()> {
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    refund_basket_legs(
+        &context.accounts.payer.to_account_info(),
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.offer.to_account_info(),
+        &signer_seeds,
+        &context.accounts.offer.extra_legs_a,
+        context.remaining_accounts,
+        &context.accounts.token_program_a,
+        &context.accounts.associated_token_program,
+        &context.accounts.system_program,
+    )
+}
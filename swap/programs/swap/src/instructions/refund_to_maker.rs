@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{error::ErrorCode, MakerProfile, Offer, OfferCancelled, SwapConfig};
+
+use super::empty_and_close_vault;
+
+/// 仲裁报价退款给创建者的账户结构
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundToMaker<'info> {
+    /// 报价创建者（共同处置或争议超时后单方面取回时需签名，接收退款和租金）
+    /// CHECK: 由报价的has_one约束校验，签名状态在处理函数中检查
+    #[account(mut)]
+    pub maker: UncheckedAccount<'info>,
+
+    /// 报价指定的接受者（与创建者共同处置时需签名）
+    /// CHECK: 必须与报价的指定接受者一致，签名状态在处理函数中检查
+    #[account(constraint = offer.allowed_taker == Some(taker.key()) @ ErrorCode::TakerNotAllowed)]
+    pub taker: UncheckedAccount<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+    /// 报价创建者的代币A账户（接收退回的代币）
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 报价状态账户（处置后关闭并将租金返还给创建者）
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ ErrorCode::NotMaker,
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        seeds = [b"offer", maker.key().as_ref(), offer.offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 报价创建者档案账户（更新未关闭的报价数量）
+    #[account(
+        mut,
+        seeds = [b"maker_profile", maker.key().as_ref()],
+        bump = maker_profile.bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
+
+    /// 报价的仲裁人（仲裁处置时需签名）
+    #[account(constraint = offer.arbiter == Some(arbiter.key()) @ ErrorCode::NotArbiter)]
+    pub arbiter: Option<Signer<'info>>,
+
+    /// 代币A的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// 全局配置账户（协议暂停时不能退款）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+}
+
+/// 将仲裁报价托管的代币A退还给创建者
+///
+/// 由仲裁人或创建者与接受者共同签名触发。
+///
+/// 争议截止时间过后仲裁人仍未处置的，创建者无需仲裁人或接受者签名即可单方面取回，
+/// 避免仲裁人失联时代币被永久锁定。因此接受者必须在截止时间之前完成链下支付
+/// 并取得放款，否则创建者可在截止后取回托管的代币A。
+/// 托管账户和报价账户关闭后租金返还给创建者，并发出撤销事件
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn refund_to_maker<'info>(
+    context: Context<'_, '_, 'info, 'info, RefundToMaker<'info>>,
+) -> Result<()> {
+    let offer = &context.accounts.offer;
+    require!(offer.is_arbitrated(), ErrorCode::OfferNotArbitrated);
+
+    let maker_signed = context.accounts.maker.is_signer;
+    require!(
+        context.accounts.arbiter.is_some()
+            || (maker_signed && context.accounts.taker.is_signer)
+            || (maker_signed && offer.is_dispute_expired(Clock::get()?.unix_timestamp)),
+        ErrorCode::NotAuthorizedToResolve
+    );
+    context.accounts.maker_profile.close_offer();
+
+    empty_and_close_vault(
+        offer,
+        &context.accounts.vault,
+        &context.accounts.maker_token_account_a,
+        &context.accounts.token_mint_a,
+        &context.accounts.token_program_a,
+        &context.accounts.maker.to_account_info(),
+        context.remaining_accounts,
+    )?;
+
+    let ctx = &context;
+    emit_cpi!(OfferCancelled::new(&ctx.accounts.offer));
+    Ok(())
+}
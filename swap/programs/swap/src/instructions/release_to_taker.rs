@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{error::ErrorCode, MakerProfile, Offer, OfferTaken, SwapConfig};
+
+use super::{empty_and_close_vault, transfer_tokens};
+
+/// 仲裁报价放款给接受者的账户结构
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseToTaker<'info> {
+    /// 报价创建者（与接受者共同处置时需签名，接收报价账户和托管账户的租金）
+    /// CHECK: 由报价的has_one约束校验，签名状态在处理函数中检查
+    #[account(mut)]
+    pub maker: UncheckedAccount<'info>,
+
+    /// 报价指定的接受者（与创建者共同处置时需签名）
+    /// CHECK: 必须与报价的指定接受者一致，签名状态在处理函数中检查
+    #[account(constraint = offer.allowed_taker == Some(taker.key()) @ ErrorCode::TakerNotAllowed)]
+    pub taker: UncheckedAccount<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+    /// 接受者代币A的关联账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 报价状态账户（处置后关闭并将租金返还给创建者）
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ ErrorCode::NotMaker,
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        seeds = [b"offer", maker.key().as_ref(), offer.offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 报价创建者档案账户（更新成交统计）
+    #[account(
+        mut,
+        seeds = [b"maker_profile", maker.key().as_ref()],
+        bump = maker_profile.bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
+
+    /// 报价的仲裁人（仲裁处置时需签名）
+    #[account(constraint = offer.arbiter == Some(arbiter.key()) @ ErrorCode::NotArbiter)]
+    pub arbiter: Option<Signer<'info>>,
+
+    /// 代币A的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// 全局配置账户（协议暂停时不能放款）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 手续费接收者的代币A账户（代币B在链下结算，手续费固定从代币A收取；
+    /// 不收取手续费时可不传）
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_recipient @ ErrorCode::WrongFeeAccount,
+        constraint = fee_token_account.mint == token_mint_a.key() @ ErrorCode::WrongFeeAccount
+    )]
+    pub fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+}
+
+/// 将仲裁报价托管的代币A放款给接受者
+///
+/// 链下的代币B结算完成后，由仲裁人或创建者与接受者共同签名触发；
+/// 代币B不经过程序，协议手续费无论配置的收费方向如何都从托管的代币A中扣除，
+/// 其余全部转给接受者。托管账户和报价账户关闭后租金返还给创建者。
+/// 放款视为报价全部成交，发出成交事件
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn release_to_taker<'info>(
+    context: Context<'_, '_, 'info, 'info, ReleaseToTaker<'info>>,
+) -> Result<()> {
+    require!(
+        context.accounts.offer.is_arbitrated(),
+        ErrorCode::OfferNotArbitrated
+    );
+    require!(
+        context.accounts.arbiter.is_some()
+            || (context.accounts.maker.is_signer && context.accounts.taker.is_signer),
+        ErrorCode::NotAuthorizedToResolve
+    );
+    // 放款视为报价全部成交
+    context.accounts.maker_profile.record_fill(true)?;
+
+    send_fee_from_vault(&context)?;
+    context.accounts.vault.reload()?;
+
+    empty_and_close_vault(
+        &context.accounts.offer,
+        &context.accounts.vault,
+        &context.accounts.taker_token_account_a,
+        &context.accounts.token_mint_a,
+        &context.accounts.token_program_a,
+        &context.accounts.maker.to_account_info(),
+        context.remaining_accounts,
+    )?;
+
+    let ctx = &context;
+    emit_cpi!(OfferTaken::new(
+        &ctx.accounts.offer,
+        ctx.accounts.taker.key(),
+        ctx.accounts.offer.token_a_remaining_amount,
+        ctx.accounts.offer.token_b_remaining_amount
+    ));
+    Ok(())
+}
+
+/// 按托管账户的余额计算协议手续费，并从托管账户转入手续费账户
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn send_fee_from_vault<'info>(
+    context: &Context<'_, '_, 'info, 'info, ReleaseToTaker<'info>>,
+) -> Result<()> {
+    let fee = context
+        .accounts
+        .config
+        .protocol_fee(context.accounts.vault.amount)?;
+    if fee == 0 {
+        return Ok(());
+    }
+
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    transfer_tokens(
+        &context.accounts.vault,
+        context
+            .accounts
+            .fee_token_account
+            .as_deref()
+            .ok_or(ErrorCode::WrongFeeAccount)?,
+        &fee,
+        &context.accounts.token_mint_a,
+        &context.accounts.offer.to_account_info(),
+        &context.accounts.token_program_a,
+        &signer_seeds,
+        context.remaining_accounts,
+    )
+}
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, Pool};
+
+use super::{token_account_balance, transfer_tokens};
+
+/// 从流动性池取出流动性的账户结构
+///
+/// 协议暂停时仍可取出流动性
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    /// 流动性提供者（签名者）
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    #[account(mint::token_program = token_program_b)]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 流动性池账户
+    #[account(
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        has_one = token_mint_b @ ErrorCode::WrongTokenMint,
+        has_one = lp_mint @ ErrorCode::WrongTokenMint,
+        seeds = [b"pool", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// LP代币的Mint账户
+    #[account(mut, mint::token_program = token_program_a)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// 代币A的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 代币B的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 提供者代币A的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = provider,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program_a
+    )]
+    pub provider_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 提供者代币B的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = provider,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program_b
+    )]
+    pub provider_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 提供者LP代币的关联账户
+    #[account(
+        mut,
+        associated_token::mint = lp_mint,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program_a
+    )]
+    pub provider_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+    /// 代币A（及LP代币）的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序接口
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 销毁LP代币并按份额取出池中的两种代币
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `lp_amount` - 销毁的LP代币数量
+/// * `min_token_a_amount` - 至少要收到的代币A数量（扣除转账手续费后）
+/// * `min_token_b_amount` - 至少要收到的代币B数量（扣除转账手续费后）
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn remove_liquidity<'info>(
+    context: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
+    lp_amount: u64,
+    min_token_a_amount: u64,
+    min_token_b_amount: u64,
+) -> Result<()> {
+    let (token_a_amount, token_b_amount) = Pool::withdraw_amounts(
+        lp_amount,
+        context.accounts.vault_a.amount,
+        context.accounts.vault_b.amount,
+        context.accounts.lp_mint.supply,
+    )?;
+    require!(
+        token_a_amount > 0 && token_b_amount > 0,
+        ErrorCode::InsufficientLiquidity
+    );
+
+    burn(
+        CpiContext::new(
+            context.accounts.token_program_a.to_account_info(),
+            Burn {
+                mint: context.accounts.lp_mint.to_account_info(),
+                from: context.accounts.provider_lp_token_account.to_account_info(),
+                authority: context.accounts.provider.to_account_info(),
+            },
+        ),
+        lp_amount,
+    )?;
+
+    let seeds = &[
+        b"pool",
+        context.accounts.pool.token_mint_a.as_ref(),
+        context.accounts.pool.token_mint_b.as_ref(),
+        &[context.accounts.pool.bump],
+    ];
+    let signer_seeds = [&seeds[..]];
+
+    let provider_token_a_balance_before = context.accounts.provider_token_account_a.amount;
+    let provider_token_b_balance_before = context.accounts.provider_token_account_b.amount;
+
+    transfer_tokens(
+        &context.accounts.vault_a,
+        &context.accounts.provider_token_account_a,
+        &token_a_amount,
+        &context.accounts.token_mint_a,
+        &context.accounts.pool.to_account_info(),
+        &context.accounts.token_program_a,
+        &signer_seeds,
+        context.remaining_accounts,
+    )?;
+    transfer_tokens(
+        &context.accounts.vault_b,
+        &context.accounts.provider_token_account_b,
+        &token_b_amount,
+        &context.accounts.token_mint_b,
+        &context.accounts.pool.to_account_info(),
+        &context.accounts.token_program_b,
+        &signer_seeds,
+        context.remaining_accounts,
+    )?;
+
+    let token_a_received =
+        token_account_balance(&context.accounts.provider_token_account_a.to_account_info())?
+            .checked_sub(provider_token_a_balance_before)
+            .ok_or(ErrorCode::MathOverflow)?;
+    let token_b_received =
+        token_account_balance(&context.accounts.provider_token_account_b.to_account_info())?
+            .checked_sub(provider_token_b_balance_before)
+            .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        token_a_received >= min_token_a_amount && token_b_received >= min_token_b_amount,
+        ErrorCode::PoolSlippageExceeded
+    );
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{self, MetadataAccount},
+    token_interface::Mint,
+};
+
+use crate::{bps_of, error::ErrorCode};
+
+/// 创作者分成比例的分母（Metaplex创作者份额之和为100）
+const CREATOR_SHARE_DENOMINATOR: u128 = 100;
+
+/// 一笔应支付给NFT创作者的版税
+pub struct RoyaltyPayment {
+    /// 已验证的创作者地址
+    pub creator: Pubkey,
+    /// 应支付的代币B数量
+    pub amount: u64,
+}
+
+/// 代币是否为NFT（精度为0且供应量为1）
+pub fn is_nft(mint: &InterfaceAccount<Mint>) -> bool {
+    mint.decimals == 0 && mint.supply == 1
+}
+
+/// 按NFT元数据计算本次成交应支付给各已验证创作者的版税
+///
+/// 版税总额为代币B数量乘以元数据中的`seller_fee_basis_points`，再按创作者份额分配；
+/// 未验证创作者的份额不支付。元数据账户不存在（非Metaplex NFT）时不收取版税
+///
+/// # 参数
+/// * `metadata` - 代币A的Metaplex元数据PDA
+/// * `token_b_amount` - 本次支付的代币B数量
+///
+/// # 返回值
+/// * `Result<Vec<RoyaltyPayment>>` - 每位已验证创作者的版税
+pub fn creator_royalties(
+    metadata: &AccountInfo,
+    token_b_amount: u64,
+) -> Result<Vec<RoyaltyPayment>> {
+    if metadata.data_is_empty() {
+        return Ok(Vec::new());
+    }
+    require_keys_eq!(
+        *metadata.owner,
+        metadata::ID,
+        ErrorCode::WrongMetadataAccount
+    );
+
+    let metadata = MetadataAccount::try_deserialize(&mut &metadata.try_borrow_data()?[..])?;
+    let royalty = bps_of(token_b_amount, metadata.seller_fee_basis_points)?;
+    let creators = metadata.creators.as_deref().unwrap_or_default();
+
+    Ok(creators
+        .iter()
+        .filter(|creator| creator.verified)
+        .map(|creator| RoyaltyPayment {
+            creator: creator.address,
+            amount: (royalty as u128 * creator.share as u128 / CREATOR_SHARE_DENOMINATOR) as u64,
+        })
+        .filter(|payment| payment.amount > 0)
+        .collect())
+}
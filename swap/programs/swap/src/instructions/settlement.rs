@@ -0,0 +1,331 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, FeeSide, MakerProfile, Offer, SwapConfig};
+
+use super::{
+    is_native_mint, transfer_lamports, transfer_lamports_from_pda, transfer_tokens,
+    transfer_tokens_exact, RoyaltyPayment,
+};
+
+/// 批量成交时每个报价在remaining_accounts中占用的账户数量
+///
+/// 每组依次为 [报价账户, 创建者账户, 托管账户, 创建者代币B账户, 创建者档案账户]，
+/// 所有账户组之后可追加TransferHook所需账户
+pub const OFFER_GROUP_ACCOUNTS: usize = 5;
+
+/// 单笔报价成交所涉及的账户
+///
+/// take_offer、take_best与take_offers共用同一套结算逻辑，前者从指令账户结构取得，
+/// 后两者从remaining_accounts中逐个报价解析得到。
+/// 代币为原生SOL的一侧不需要代币账户，直接转移lamports
+pub struct OfferSettlement<'a, 'info> {
+    /// 接受者（签名者）
+    pub taker: &'a AccountInfo<'info>,
+    /// 报价创建者
+    pub maker: &'a AccountInfo<'info>,
+    /// 报价状态账户
+    pub offer: &'a Account<'info, Offer>,
+    /// 代币A的托管账户
+    pub vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    /// 代币A的Mint账户
+    pub token_mint_a: &'a InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    pub token_mint_b: &'a InterfaceAccount<'info, Mint>,
+    /// 接受者的代币A账户
+    pub taker_token_account_a: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    /// 接受者的代币B账户
+    pub taker_token_account_b: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    /// 报价创建者的代币B账户
+    pub maker_token_account_b: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    /// 全局配置账户
+    pub config: &'a Account<'info, SwapConfig>,
+    /// 手续费接收者的代币账户
+    pub fee_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    /// 手续费接收者（手续费以原生SOL收取时使用）
+    pub fee_recipient: Option<&'a AccountInfo<'info>>,
+    /// 推荐者的代币B账户
+    pub referrer_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    /// 推荐费率（基点），仅在提供推荐者账户时生效
+    pub referral_bps: u16,
+    /// 代币A为NFT时本次成交应支付的创作者版税
+    pub royalties: &'a [RoyaltyPayment],
+    /// 代币A的代币程序
+    pub token_program_a: &'a Interface<'info, TokenInterface>,
+    /// 代币B的代币程序
+    pub token_program_b: &'a Interface<'info, TokenInterface>,
+    /// 系统程序
+    pub system_program: &'a AccountInfo<'info>,
+    /// 指令的remaining_accounts（TransferHook额外账户从中查找）
+    pub remaining_accounts: &'info [AccountInfo<'info>],
+}
+
+/// 取出非原生SOL一侧必需的账户
+fn required<T>(account: Option<T>) -> Result<T> {
+    account.ok_or_else(|| ErrorCode::MissingTokenAccount.into())
+}
+
+impl<'a, 'info> OfferSettlement<'a, 'info> {
+    /// 将本次成交的代币B发送给报价创建者
+    ///
+    /// 手续费从代币B收取时，先将手续费转入手续费账户；代币A为NFT时向已验证创作者支付版税，
+    /// 剩余部分转给创建者。提供推荐者账户时，推荐费由接受者在`token_b_amount`之外额外支付，
+    /// 推荐者账户不经校验，因此不能从创建者所得中扣除。
+    /// 代币B带有转账手续费时由接受者额外承担，保证创建者、手续费账户、推荐者与创作者按数量足额到账
+    pub fn pay_maker(&self, token_b_amount: u64) -> Result<()> {
+        let fee = match self.config.fee_side {
+            FeeSide::TokenB => self.config.protocol_fee(token_b_amount)?,
+            FeeSide::TokenA => 0,
+        };
+        let referral_fee = match self.referrer_token_account {
+            Some(_) => self
+                .config
+                .referral_fee(token_b_amount, self.referral_bps)?,
+            None => 0,
+        };
+        let royalty_total: u64 = self.royalties.iter().map(|royalty| royalty.amount).sum();
+        let maker_amount = token_b_amount
+            .checked_sub(fee)
+            .and_then(|amount| amount.checked_sub(royalty_total))
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if is_native_mint(&self.token_mint_b.key()) {
+            require!(
+                self.referrer_token_account.is_none(),
+                ErrorCode::NativeMintNotSupported
+            );
+            if fee > 0 {
+                transfer_lamports(
+                    self.taker,
+                    self.fee_recipient.ok_or(ErrorCode::WrongFeeAccount)?,
+                    fee,
+                    self.system_program,
+                )?;
+            }
+            for royalty in self.royalties {
+                transfer_lamports(
+                    self.taker,
+                    self.royalty_account(royalty)?,
+                    royalty.amount,
+                    self.system_program,
+                )?;
+            }
+            return transfer_lamports(self.taker, self.maker, maker_amount, self.system_program);
+        }
+
+        let taker_token_account_b = required(self.taker_token_account_b)?;
+        if fee > 0 {
+            transfer_tokens_exact(
+                taker_token_account_b,
+                required(self.fee_token_account)?,
+                &fee,
+                self.token_mint_b,
+                self.taker,
+                self.token_program_b,
+                &[],
+                self.remaining_accounts,
+            )?;
+        }
+
+        if let Some(referrer_token_account) = self.referrer_token_account {
+            if referral_fee > 0 {
+                transfer_tokens_exact(
+                    taker_token_account_b,
+                    referrer_token_account,
+                    &referral_fee,
+                    self.token_mint_b,
+                    self.taker,
+                    self.token_program_b,
+                    &[],
+                    self.remaining_accounts,
+                )?;
+            }
+        }
+
+        for royalty in self.royalties {
+            let creator_token_account =
+                InterfaceAccount::<TokenAccount>::try_from(self.royalty_account(royalty)?)?;
+            transfer_tokens_exact(
+                taker_token_account_b,
+                &creator_token_account,
+                &royalty.amount,
+                self.token_mint_b,
+                self.taker,
+                self.token_program_b,
+                &[],
+                self.remaining_accounts,
+            )?;
+        }
+
+        transfer_tokens_exact(
+            taker_token_account_b,
+            required(self.maker_token_account_b)?,
+            &maker_amount,
+            self.token_mint_b,
+            self.taker,
+            self.token_program_b,
+            &[],
+            self.remaining_accounts,
+        )
+    }
+
+    /// 在remaining_accounts中查找创作者接收版税的账户
+    ///
+    /// 代币B为原生SOL时为创作者本身，否则为创作者的代币B关联账户
+    fn royalty_account(&self, royalty: &RoyaltyPayment) -> Result<&'info AccountInfo<'info>> {
+        let address = if is_native_mint(&self.token_mint_b.key()) {
+            royalty.creator
+        } else {
+            get_associated_token_address_with_program_id(
+                &royalty.creator,
+                &self.token_mint_b.key(),
+                &self.token_program_b.key(),
+            )
+        };
+        self.remaining_accounts
+            .iter()
+            .find(|account| *account.key == address)
+            .ok_or_else(|| ErrorCode::MissingRoyaltyAccount.into())
+    }
+
+    /// 从托管账户释放代币A给接受者，全部成交时关闭托管账户
+    ///
+    /// 最后一笔成交提取托管账户全部余额，手续费从代币A收取时先转入手续费账户；
+    /// 托管账户关闭后租金返还给接受者
+    pub fn release_token_a(&self, token_a_amount: u64) -> Result<()> {
+        if is_native_mint(&self.token_mint_a.key()) {
+            return self.release_lamports(token_a_amount);
+        }
+
+        let vault = required(self.vault)?;
+        let is_final_fill = token_a_amount == self.offer.token_a_remaining_amount;
+        let release_amount = if is_final_fill {
+            vault.amount
+        } else {
+            token_a_amount
+        };
+        let fee = match self.config.fee_side {
+            FeeSide::TokenA => self.config.protocol_fee(release_amount)?,
+            FeeSide::TokenB => 0,
+        };
+
+        let seeds = self.offer.signer_seeds();
+        let signer_seeds = [&seeds.as_seeds()[..]];
+
+        if fee > 0 {
+            transfer_tokens(
+                vault,
+                required(self.fee_token_account)?,
+                &fee,
+                self.token_mint_a,
+                &self.offer.to_account_info(),
+                self.token_program_a,
+                &signer_seeds,
+                self.remaining_accounts,
+            )?;
+        }
+
+        transfer_tokens(
+            vault,
+            required(self.taker_token_account_a)?,
+            &(release_amount - fee),
+            self.token_mint_a,
+            &self.offer.to_account_info(),
+            self.token_program_a,
+            &signer_seeds,
+            self.remaining_accounts,
+        )?;
+
+        if !is_final_fill {
+            return Ok(());
+        }
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            CloseAccount {
+                account: vault.to_account_info(),
+                destination: self.taker.clone(),
+                authority: self.offer.to_account_info(),
+            },
+            &signer_seeds,
+        ))
+    }
+
+    /// 带解锁计划的报价成交时代币A留在托管账户中，由接受者之后逐步提取
+    ///
+    /// 手续费从代币A收取时在成交时一次性转入手续费账户，返回锁定给接受者的数量
+    pub fn lock_token_a(&self, token_a_amount: u64) -> Result<u64> {
+        let fee = match self.config.fee_side {
+            FeeSide::TokenA => self.config.protocol_fee(token_a_amount)?,
+            FeeSide::TokenB => 0,
+        };
+        if fee == 0 {
+            return Ok(token_a_amount);
+        }
+
+        let seeds = self.offer.signer_seeds();
+        let signer_seeds = [&seeds.as_seeds()[..]];
+
+        transfer_tokens(
+            required(self.vault)?,
+            required(self.fee_token_account)?,
+            &fee,
+            self.token_mint_a,
+            &self.offer.to_account_info(),
+            self.token_program_a,
+            &signer_seeds,
+            self.remaining_accounts,
+        )?;
+        Ok(token_a_amount - fee)
+    }
+
+    /// 从报价账户托管的lamports中释放原生SOL给接受者
+    ///
+    /// 手续费从代币A收取时先转给手续费接收者；报价账户的租金在报价关闭时返还给创建者
+    fn release_lamports(&self, token_a_amount: u64) -> Result<()> {
+        let fee = match self.config.fee_side {
+            FeeSide::TokenA => self.config.protocol_fee(token_a_amount)?,
+            FeeSide::TokenB => 0,
+        };
+
+        let offer = self.offer.to_account_info();
+        if fee > 0 {
+            transfer_lamports_from_pda(
+                &offer,
+                self.fee_recipient.ok_or(ErrorCode::WrongFeeAccount)?,
+                fee,
+            )?;
+        }
+        transfer_lamports_from_pda(&offer, self.taker, token_a_amount - fee)
+    }
+}
+
+/// 校验并反序列化remaining_accounts中的报价账户
+///
+/// 按报价记录的创建者、ID和bump重新推导PDA，与take_offer账户结构中的seeds约束等价
+pub fn offer_account<'info>(account: &'info AccountInfo<'info>) -> Result<Account<'info, Offer>> {
+    let offer: Account<Offer> = Account::try_from(account)?;
+    let offer_key = Pubkey::create_program_address(&offer.signer_seeds().as_seeds(), &crate::ID)
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::ConstraintSeeds))?;
+    require_keys_eq!(
+        *account.key,
+        offer_key,
+        anchor_lang::error::ErrorCode::ConstraintSeeds
+    );
+    Ok(offer)
+}
+
+/// 校验并反序列化报价创建者的档案账户
+///
+/// 档案账户只能由本程序以创建者为种子创建，账户归属和记录的创建者一致即可确认地址
+pub fn maker_profile_account<'info>(
+    account: &'info AccountInfo<'info>,
+    maker: &AccountInfo<'info>,
+) -> Result<Account<'info, MakerProfile>> {
+    let maker_profile: Account<MakerProfile> = Account::try_from(account)?;
+    require_keys_eq!(maker_profile.maker, *maker.key, ErrorCode::NotMaker);
+    Ok(maker_profile)
+}
@@ -1,3 +1,22 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    token::spl_token::native_mint,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state::DefaultAccountState, permanent_delegate::PermanentDelegate,
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::{Account as SplTokenAccount, AccountState, Mint as SplMint},
+    },
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, Offer, SwapConfig};
+
 /// 执行代币转账操作
 ///
 /// # 参数说明
@@ -5,32 +24,224 @@
 /// * `to` - 目标代币账户
 /// * `amount` - 转账金额
 /// * `mint` - 代币铸造信息
-/// * `authority` - 转账授权签名者
+/// * `authority` - 转账授权账户（用户签名者或PDA）
 /// * `token_program` - 代币程序接口
+/// * `signer_seeds` - PDA授权时使用的签名种子，用户签名时传空切片
+/// * `remaining_accounts` - 指令的remaining_accounts，代币启用TransferHook扩展时
+///   从中按地址查找钩子程序所需的额外账户并随CPI一并转发
 ///
 /// # 返回值
 /// 返回Result类型，成功时无错误，失败时返回错误信息
-use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{
-    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
-};
-
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_tokens<'info>(
     from: &InterfaceAccount<'info, TokenAccount>,
     to: &InterfaceAccount<'info, TokenAccount>,
     amount: &u64,
     mint: &InterfaceAccount<'info, Mint>,
-    authority: &Signer<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer_seeds: &[&[&[u8]]],
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program.key,
+        from.to_account_info(),
+        mint.to_account_info(),
+        to.to_account_info(),
+        authority.clone(),
+        remaining_accounts,
+        *amount,
+        mint.decimals,
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+/// 执行代币转账，并保证接收方实际到账数量与`amount`完全一致
+///
+/// 对带有TransferFee扩展的Token-2022代币，按当前epoch的费率上调转出数量，
+/// 由付款方承担转账手续费；转账后校验接收方余额变化，不一致时返回错误
+///
+/// # 参数说明
+/// 与`transfer_tokens`相同，`amount`为接收方应到账的数量
+///
+/// # 返回值
+/// 返回Result类型，成功时无错误，失败时返回错误信息
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_tokens_exact<'info>(
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    amount: &u64,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer_seeds: &[&[&[u8]]],
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let balance_before = token_account_balance(&to.to_account_info())?;
+    let gross_amount = gross_up_for_transfer_fee(mint, *amount)?;
+
+    transfer_tokens(
+        from,
+        to,
+        &gross_amount,
+        mint,
+        authority,
+        token_program,
+        signer_seeds,
+        remaining_accounts,
+    )?;
+
+    let received = token_account_balance(&to.to_account_info())?
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::TransferAmountMismatch)?;
+    require_eq!(received, *amount, ErrorCode::TransferAmountMismatch);
+
+    Ok(())
+}
+
+/// 计算接收方实际到账`net_amount`所需的转出数量
+///
+/// 非Token-2022代币或未启用TransferFee扩展时直接返回`net_amount`
+pub fn gross_up_for_transfer_fee(mint: &InterfaceAccount<Mint>, net_amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(net_amount);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<SplMint>::unpack(&mint_data)?;
+    match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .get_epoch_fee(Clock::get()?.epoch)
+            .calculate_pre_fee_amount(net_amount)
+            .ok_or_else(|| ErrorCode::MathOverflow.into()),
+        Err(_) => Ok(net_amount),
+    }
+}
+
+/// 检查代币是否允许在报价中使用
+///
+/// 先按全局代币名单检查；白名单中的代币视为已由管理员认可，不再检查危险设置。
+/// 其余代币不得设置冻结权限，Token-2022代币还不得启用永久代理或默认冻结新账户
+pub fn check_mint(config: &SwapConfig, mint: &InterfaceAccount<Mint>) -> Result<()> {
+    if config.check_mint_list(&mint.key())? {
+        return Ok(());
+    }
+    require!(mint.freeze_authority.is_none(), ErrorCode::DangerousMint);
+
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<SplMint>::unpack(&mint_data)?;
+    if let Ok(permanent_delegate) = mint_state.get_extension::<PermanentDelegate>() {
+        require!(
+            Option::<Pubkey>::from(permanent_delegate.delegate).is_none(),
+            ErrorCode::DangerousMint
+        );
+    }
+    if let Ok(default_state) = mint_state.get_extension::<DefaultAccountState>() {
+        require!(
+            default_state.state != AccountState::Frozen as u8,
+            ErrorCode::DangerousMint
+        );
+    }
+    Ok(())
+}
+
+/// 直接从账户数据读取代币账户的最新余额（兼容两种代币程序）
+pub fn token_account_balance(token_account: &AccountInfo) -> Result<u64> {
+    let data = token_account.try_borrow_data()?;
+    Ok(StateWithExtensions::<SplTokenAccount>::unpack(&data)?
+        .base
+        .amount)
+}
+
+/// 代币是否为原生SOL
+///
+/// 报价以wSOL的Mint地址表示原生SOL，但不经过包装，直接托管和转移lamports
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == native_mint::ID
+}
+
+/// 通过系统程序转移lamports，`from`必须是签名的系统账户
+pub fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    transfer(
+        CpiContext::new(
+            system_program.clone(),
+            Transfer {
+                from: from.clone(),
+                to: to.clone(),
+            },
+        ),
+        amount,
+    )
+}
+
+/// 从本程序拥有的PDA中直接转出lamports（带数据的账户不能通过系统程序转账）
+pub fn transfer_lamports_from_pda(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    from.sub_lamports(amount)?;
+    to.add_lamports(amount)?;
+    Ok(())
+}
+
+/// 报价账户中托管的lamports，即超出租金豁免下限的部分
+pub fn escrowed_lamports(offer: &AccountInfo) -> Result<u64> {
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(offer.data_len());
+    Ok(offer.lamports().saturating_sub(rent_exempt_lamports))
+}
+
+/// 将报价托管账户的全部余额转给`destination`，并关闭托管账户，租金返还给`rent_destination`
+///
+/// # 参数
+/// * `offer` - 报价账户（托管账户的权限PDA）
+/// * `vault` - 代币A的托管账户
+/// * `destination` - 接收代币的账户
+/// * `mint` - 代币A的Mint账户
+/// * `token_program` - 代币A的代币程序
+/// * `rent_destination` - 接收托管账户租金的账户
+/// * `remaining_accounts` - 指令的remaining_accounts（TransferHook额外账户从中查找）
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn empty_and_close_vault<'info>(
+    offer: &Account<'info, Offer>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
     token_program: &Interface<'info, TokenInterface>,
+    rent_destination: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
 ) -> Result<()> {
-    let transfer_accounts_options = TransferChecked {
-        from: from.to_account_info(),
-        mint: mint.to_account_info(),
-        to: to.to_account_info(),
-        authority: authority.to_account_info(),
-    };
+    let seeds = offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
 
-    let cpi_context = CpiContext::new(token_program.to_account_info(), transfer_accounts_options);
+    transfer_tokens(
+        vault,
+        destination,
+        &vault.amount,
+        mint,
+        &offer.to_account_info(),
+        token_program,
+        &signer_seeds,
+        remaining_accounts,
+    )?;
 
-    transfer_checked(cpi_context, *amount, mint.decimals)
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault.to_account_info(),
+            destination: rent_destination.clone(),
+            authority: offer.to_account_info(),
+        },
+        &signer_seeds,
+    ))
 }
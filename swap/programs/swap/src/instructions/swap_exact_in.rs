@@ -0,0 +1,196 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, Pool, SwapConfig};
+
+use super::{check_mint, token_account_balance, transfer_tokens};
+
+/// 通过流动性池兑换代币的账户结构
+#[derive(Accounts)]
+pub struct SwapExactIn<'info> {
+    /// 兑换者（签名者）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 全局配置账户（协议暂停时不能兑换）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    #[account(mint::token_program = token_program_b)]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 流动性池账户
+    #[account(
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        has_one = token_mint_b @ ErrorCode::WrongTokenMint,
+        seeds = [b"pool", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// 代币A的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 代币B的托管账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 兑换者代币A的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_a
+    )]
+    pub user_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 兑换者代币B的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_b
+    )]
+    pub user_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 系统程序
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序接口
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 以确定的输入数量通过流动性池兑换代币
+///
+/// 输出数量按恒定乘积公式以池子实际收到的输入数量计算，手续费留在池中归LP所有；
+/// 两种代币都须仍然通过全局配置的代币检查
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `amount_in` - 转入池子的代币数量
+/// * `min_amount_out` - 至少要收到的输出代币数量（扣除转账手续费后）
+/// * `a_to_b` - true表示以代币A兑换代币B，false表示以代币B兑换代币A
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn swap_exact_in<'info>(
+    context: Context<'_, '_, 'info, 'info, SwapExactIn<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    a_to_b: bool,
+) -> Result<()> {
+    require!(amount_in > 0, ErrorCode::InvalidAmount);
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+
+    let accounts = &context.accounts;
+    let (user_in, vault_in, mint_in, token_program_in) = if a_to_b {
+        (
+            &accounts.user_token_account_a,
+            &accounts.vault_a,
+            &accounts.token_mint_a,
+            &accounts.token_program_a,
+        )
+    } else {
+        (
+            &accounts.user_token_account_b,
+            &accounts.vault_b,
+            &accounts.token_mint_b,
+            &accounts.token_program_b,
+        )
+    };
+    let (user_out, vault_out, mint_out, token_program_out) = if a_to_b {
+        (
+            &accounts.user_token_account_b,
+            &accounts.vault_b,
+            &accounts.token_mint_b,
+            &accounts.token_program_b,
+        )
+    } else {
+        (
+            &accounts.user_token_account_a,
+            &accounts.vault_a,
+            &accounts.token_mint_a,
+            &accounts.token_program_a,
+        )
+    };
+    let reserve_in = vault_in.amount;
+    let reserve_out = vault_out.amount;
+
+    transfer_tokens(
+        user_in,
+        vault_in,
+        &amount_in,
+        mint_in,
+        &accounts.user.to_account_info(),
+        token_program_in,
+        &[],
+        context.remaining_accounts,
+    )?;
+
+    // 以池子实际到账的数量计算输出，Token-2022转账手续费会使其少于转出数量
+    let amount_in_received = token_account_balance(&vault_in.to_account_info())?
+        .checked_sub(reserve_in)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let amount_out = accounts
+        .pool
+        .swap_amount_out(amount_in_received, reserve_in, reserve_out)?;
+
+    let seeds = &[
+        b"pool",
+        accounts.pool.token_mint_a.as_ref(),
+        accounts.pool.token_mint_b.as_ref(),
+        &[accounts.pool.bump],
+    ];
+    let signer_seeds = [&seeds[..]];
+
+    let user_out_balance_before = user_out.amount;
+    transfer_tokens(
+        vault_out,
+        user_out,
+        &amount_out,
+        mint_out,
+        &accounts.pool.to_account_info(),
+        token_program_out,
+        &signer_seeds,
+        context.remaining_accounts,
+    )?;
+
+    let amount_out_received = token_account_balance(&user_out.to_account_info())?
+        .checked_sub(user_out_balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_gte!(
+        amount_out_received,
+        min_amount_out,
+        ErrorCode::PoolSlippageExceeded
+    );
+
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, Market, OfferPartiallyTaken, OfferTaken, SwapConfig};
+
+use super::{
+    associated_token_account, check_mint, is_nft, maker_profile_account, offer_account,
+    token_account_balance, vault_account, OfferSettlement, OFFER_GROUP_ACCOUNTS,
+};
+
+/// 按最优价格成交市场报价的账户结构
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeBest<'info> {
+    /// 交易执行者，需提供签名
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    #[account(mint::token_program = token_program_b)]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 交易执行者代币A的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 交易执行者代币B的关联账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 交易对市场账户
+    #[account(
+        mut,
+        seeds = [b"market", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// 全局配置账户（协议暂停时不能成交）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 手续费接收者的代币账户（代币A或代币B，取决于配置的收费方向；
+    /// 不收取手续费时可不传）
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_recipient @ ErrorCode::WrongFeeAccount,
+        constraint = fee_token_account.mint
+            == config.fee_mint(&token_mint_a.key(), &token_mint_b.key()) @ ErrorCode::WrongFeeAccount
+    )]
+    pub fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 系统程序账户
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序账户
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序账户
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序账户
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 按价格从低到高依次成交市场中的报价，直到用完接受者的代币B预算
+///
+/// remaining_accounts最前面按市场索引的顺序为`offer_count`个报价各提供一组账户，
+/// 见`OFFER_GROUP_ACCOUNTS`，其后为TransferHook所需的额外账户。
+///
+/// 已关闭、已取消、已过期或上架后被修改的报价会被跳过并从市场中移除；
+/// 全部成交的报价同样从市场中移除
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `token_b_budget` - 接受者最多支付的代币B数量（不含代币B的转账手续费）
+/// * `min_token_a_amount` - 接受者至少要收到的代币A数量
+/// * `offer_count` - remaining_accounts中提供账户组的报价数量
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn take_best<'info>(
+    context: Context<'_, '_, 'info, 'info, TakeBest<'info>>,
+    token_b_budget: u64,
+    min_token_a_amount: u64,
+    offer_count: u8,
+) -> Result<()> {
+    require!(token_b_budget > 0, ErrorCode::InvalidFillAmount);
+    let offer_accounts_len = offer_count as usize * OFFER_GROUP_ACCOUNTS;
+    require!(
+        context.remaining_accounts.len() >= offer_accounts_len,
+        ErrorCode::MissingOfferAccounts
+    );
+    let (offer_accounts, hook_accounts) = context.remaining_accounts.split_at(offer_accounts_len);
+    require!(
+        !is_nft(&context.accounts.token_mint_a),
+        ErrorCode::NftOfferNotRoutable
+    );
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let taker_token_a_balance_before =
+        token_account_balance(&context.accounts.taker_token_account_a.to_account_info())?;
+
+    let mut token_b_budget_left = token_b_budget;
+    let mut delisted_offers = Vec::new();
+
+    for (entry, group) in context
+        .accounts
+        .market
+        .offers
+        .iter()
+        .zip(offer_accounts.chunks_exact(OFFER_GROUP_ACCOUNTS))
+    {
+        if token_b_budget_left == 0 {
+            break;
+        }
+        require_keys_eq!(*group[0].key, entry.offer, ErrorCode::WrongMarketAccounts);
+
+        // 报价已在市场之外被全部成交并关闭
+        if group[0].data_is_empty() {
+            delisted_offers.push(entry.offer);
+            continue;
+        }
+
+        let mut offer = offer_account(&group[0])?;
+        if offer.is_cancelled || offer.is_expired(now) || offer.revision != entry.revision {
+            delisted_offers.push(entry.offer);
+            continue;
+        }
+        require_keys_eq!(*group[1].key, offer.maker, ErrorCode::NotMaker);
+        let mut maker_profile = maker_profile_account(&group[4], &group[1])?;
+
+        let token_b_fill_amount =
+            token_b_budget_left.min(offer.current_token_b_remaining_amount(now)?);
+        // 剩余预算不足以换到最小单位的代币A时停止成交
+        let Ok(token_a_amount) = offer.token_a_for_fill(token_b_fill_amount, now) else {
+            break;
+        };
+
+        let vault = vault_account(
+            &group[2],
+            &group[0],
+            &context.accounts.token_mint_a,
+            &context.accounts.token_program_a,
+        )?;
+        let maker_token_account_b = associated_token_account(
+            &group[3],
+            &group[1],
+            &context.accounts.token_mint_b,
+            &context.accounts.taker.to_account_info(),
+            &context.accounts.token_program_b,
+            &context.accounts.associated_token_program,
+            &context.accounts.system_program,
+        )?;
+
+        let settlement = OfferSettlement {
+            taker: context.accounts.taker.as_ref(),
+            maker: &group[1],
+            offer: &offer,
+            vault: Some(&vault),
+            token_mint_a: &context.accounts.token_mint_a,
+            token_mint_b: &context.accounts.token_mint_b,
+            taker_token_account_a: Some(&context.accounts.taker_token_account_a),
+            taker_token_account_b: Some(&context.accounts.taker_token_account_b),
+            maker_token_account_b: Some(&maker_token_account_b),
+            config: &context.accounts.config,
+            fee_token_account: context.accounts.fee_token_account.as_deref(),
+            fee_recipient: None,
+            referrer_token_account: None,
+            referral_bps: 0,
+            royalties: &[],
+            token_program_a: &context.accounts.token_program_a,
+            token_program_b: &context.accounts.token_program_b,
+            system_program: context.accounts.system_program.as_ref(),
+            remaining_accounts: hook_accounts,
+        };
+        settlement.pay_maker(token_b_fill_amount)?;
+        settlement.release_token_a(token_a_amount)?;
+
+        offer.record_fill(token_a_amount, token_b_fill_amount, now)?;
+        maker_profile.record_fill(offer.is_filled())?;
+        maker_profile.exit(&crate::ID)?;
+
+        let ctx = &context;
+        let taker = ctx.accounts.taker.key();
+        if offer.is_filled() {
+            emit_cpi!(OfferTaken::new(
+                &offer,
+                taker,
+                token_a_amount,
+                token_b_fill_amount
+            ));
+        } else {
+            emit_cpi!(OfferPartiallyTaken::new(
+                &offer,
+                taker,
+                token_a_amount,
+                token_b_fill_amount
+            ));
+        }
+        token_b_budget_left -= token_b_fill_amount;
+
+        if offer.is_filled() {
+            offer.close(group[1].clone())?;
+            delisted_offers.push(entry.offer);
+        } else {
+            offer.exit(&crate::ID)?;
+        }
+    }
+
+    require!(
+        token_b_budget_left < token_b_budget,
+        ErrorCode::NoMatchingOffers
+    );
+
+    let market = &mut context.accounts.market;
+    for offer_key in &delisted_offers {
+        market.remove(offer_key)?;
+    }
+
+    context.accounts.taker_token_account_a.reload()?;
+    require_gte!(
+        context
+            .accounts
+            .taker_token_account_a
+            .amount
+            .checked_sub(taker_token_a_balance_before)
+            .ok_or(ErrorCode::MathOverflow)?,
+        min_token_a_amount,
+        ErrorCode::TokenAAmountTooLow
+    );
+
+    Ok(())
+}
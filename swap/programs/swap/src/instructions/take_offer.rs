@@ -1,16 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
-    },
+    metadata::mpl_token_metadata::accounts::Metadata as MetadataPda,
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::Offer;
+use crate::{
+    error::ErrorCode, FeeSide, MakerProfile, Offer, OfferPartiallyTaken, OfferTaken, SwapConfig,
+};
 
-use super::transfer_tokens;
+use super::{
+    creator_royalties, is_nft, settle_basket_legs, token_account_balance, OfferSettlement,
+    RoyaltyPayment,
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct TakeOffer<'info> {
     /// 交易执行者（接受报价的人），需提供签名
@@ -22,43 +26,44 @@ pub struct TakeOffer<'info> {
     pub maker: SystemAccount<'info>,
 
     /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
     pub token_mint_a: InterfaceAccount<'info, Mint>,
     /// 代币B的Mint账户
+    #[account(mint::token_program = token_program_b)]
     pub token_mint_b: InterfaceAccount<'info, Mint>,
 
-    /// 交易执行者代币A的关联账户（如果不存在则自动创建）
+    /// 交易执行者代币A的关联账户（如果不存在则自动创建，代币A为原生SOL时不传）
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = token_mint_a,
         associated_token::authority = taker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub taker_token_account_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
-    /// 交易执行者代币B的关联账户（需为可变账户）
+    /// 交易执行者代币B的关联账户（需为可变账户，代币B为原生SOL时不传）
     #[account(
         mut,
         associated_token::mint = token_mint_b,
         associated_token::authority = taker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_b
     )]
-    pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub taker_token_account_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
-    /// 报价创建者代币B的关联账户（如果不存在则自动创建）
+    /// 报价创建者代币B的关联账户（如果不存在则自动创建，代币B为原生SOL时不传）
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = token_mint_b,
         associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_b
     )]
-    pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub maker_token_account_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
-    /// 报价状态账户（执行后自动关闭并返还给创建者）
+    /// 报价状态账户（全部成交后关闭并将租金返还给创建者）
     #[account(
         mut,
-        close = maker,
         has_one = maker,
         has_one = token_mint_a,
         has_one = token_mint_b,
@@ -67,88 +72,374 @@ pub struct TakeOffer<'info> {
     )]
     pub offer: Account<'info, Offer>,
 
-    /// 代币A的托管账户（由报价创建者管理）
+    /// 报价创建者档案账户（更新成交统计）
+    #[account(
+        mut,
+        seeds = [b"maker_profile", maker.key().as_ref()],
+        bump = maker_profile.bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
+
+    /// 代币A的托管账户（由报价创建者管理，代币A为原生SOL时不传）
     #[account(
         mut,
         associated_token::mint = token_mint_a,
         associated_token::authority = offer,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
+    )]
+    vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 全局配置账户（协议暂停时不能成交）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 手续费接收者的代币账户（代币A或代币B，取决于配置的收费方向；
+    /// 收费一侧为原生SOL时不传）
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_recipient @ ErrorCode::WrongFeeAccount,
+        constraint = fee_token_account.mint
+            == config.fee_mint(&token_mint_a.key(), &token_mint_b.key()) @ ErrorCode::WrongFeeAccount
     )]
-    vault: InterfaceAccount<'info, TokenAccount>,
+    pub fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 手续费接收者（仅收费一侧为原生SOL时需要）
+    /// CHECK: 地址与配置中的手续费接收者一致，只接收lamports
+    #[account(mut, address = config.fee_recipient @ ErrorCode::WrongFeeAccount)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+
+    /// 推荐者的代币B账户（可选，由接入的前端提供，代币B为原生SOL时不支持）；
+    /// 推荐费由接受者额外支付，不影响创建者所得
+    #[account(
+        mut,
+        token::mint = token_mint_b,
+        token::token_program = token_program_b
+    )]
+    pub referrer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 代币A的Metaplex元数据账户（代币A为NFT时必须提供，用于支付创作者版税）
+    /// CHECK: 地址为代币A的元数据PDA，账户内容在结算时解析
+    #[account(
+        address = MetadataPda::find_pda(&token_mint_a.key()).0 @ ErrorCode::WrongMetadataAccount
+    )]
+    pub nft_metadata: Option<UncheckedAccount<'info>>,
 
     /// 系统程序账户
     pub system_program: Program<'info, System>,
-    /// 代币程序账户
-    pub token_program: Interface<'info, TokenInterface>,
+    /// 代币A的代币程序账户
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序账户
+    pub token_program_b: Interface<'info, TokenInterface>,
     /// 关联代币程序账户
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-/// 将期望的代币B发送给报价创建者
+/// 以指令账户构造本次成交的结算账户
+fn settlement<'a, 'info>(
+    context: &'a Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+    referral_bps: u16,
+    royalties: &'a [RoyaltyPayment],
+) -> OfferSettlement<'a, 'info> {
+    OfferSettlement {
+        taker: context.accounts.taker.as_ref(),
+        maker: context.accounts.maker.as_ref(),
+        offer: &context.accounts.offer,
+        vault: context.accounts.vault.as_ref(),
+        token_mint_a: &context.accounts.token_mint_a,
+        token_mint_b: &context.accounts.token_mint_b,
+        taker_token_account_a: context.accounts.taker_token_account_a.as_deref(),
+        taker_token_account_b: context.accounts.taker_token_account_b.as_deref(),
+        maker_token_account_b: context.accounts.maker_token_account_b.as_deref(),
+        config: &context.accounts.config,
+        fee_token_account: context.accounts.fee_token_account.as_deref(),
+        fee_recipient: context
+            .accounts
+            .fee_recipient
+            .as_ref()
+            .map(|fee_recipient| fee_recipient.as_ref()),
+        referrer_token_account: context.accounts.referrer_token_account.as_deref(),
+        referral_bps,
+        royalties,
+        token_program_a: &context.accounts.token_program_a,
+        token_program_b: &context.accounts.token_program_b,
+        system_program: context.accounts.system_program.as_ref(),
+        remaining_accounts: context.remaining_accounts,
+    }
+}
+
+/// 将本次成交的代币B发送给报价创建者
+///
+/// 手续费从代币B收取时，先将手续费转入手续费账户，剩余部分转给创建者；
+/// 提供推荐者账户时接受者按推荐费率额外支付推荐费。
+/// 代币B带有转账手续费时由接受者额外承担，保证创建者、手续费账户与推荐者按数量足额到账
 ///
 /// # 参数
 /// - `context`: 指令执行上下文，包含所有必要账户
+/// - `token_b_amount`: 本次支付的代币B数量
+/// - `referral_bps`: 推荐费率（基点），不得超过配置上限
+/// - `royalties`: 代币A为NFT时应支付给创作者的版税
 ///
 /// # 返回值
 /// - `Result<()>`: 操作成功返回Ok，失败返回错误信息
-pub fn send_wanted_tokens_to_maker(context: &Context<TakeOffer>) -> Result<()> {
-    transfer_tokens(
-        &context.accounts.taker_token_account_b,
-        &context.accounts.maker_token_account_b,
-        &context.accounts.offer.token_b_wanted_amount,
-        &context.accounts.token_mint_b,
-        &context.accounts.taker,
-        &context.accounts.token_program,
-    )
+pub fn send_wanted_tokens_to_maker<'info>(
+    context: &Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+    token_b_amount: u64,
+    referral_bps: u16,
+    royalties: &[RoyaltyPayment],
+) -> Result<()> {
+    settlement(context, referral_bps, royalties).pay_maker(token_b_amount)
 }
 
-/// 从托管账户提取代币A并关闭账户
+/// 从托管账户提取代币A，全部成交时关闭账户
 ///
 /// 执行两步操作：
-/// 1. 将托管账户中的代币A转移到交易执行者账户
-/// 2. 关闭托管账户并将租金返还给执行者
+/// 1. 将本次成交的代币A转移到交易执行者账户（最后一笔成交提取托管账户全部余额），
+///    手续费从代币A收取时先转入手续费账户
+/// 2. 全部成交时关闭托管账户并将租金返还给执行者
 ///
 /// # 参数
 /// - `context`: 指令执行上下文，包含所有必要账户
+/// - `token_a_amount`: 本次释放的代币A数量
 ///
 /// # 返回值
 /// - `Result<()>`: 操作成功返回Ok，失败返回错误信息
-pub fn withdraw_and_close_vault(context: &Context<TakeOffer>) -> Result<()> {
-    let seeds = &[
-        b"offer",
-        context.accounts.maker.to_account_info().key.as_ref(),
-        &context.accounts.offer.offer_id.to_le_bytes()[..],
-        &[context.accounts.offer.bump],
-    ];
-    let signer_seeds = [&seeds[..]];
-    let accounts = TransferChecked {
-        from: context.accounts.vault.to_account_info(),
-        to: context.accounts.taker_token_account_a.to_account_info(),
-        mint: context.accounts.token_mint_a.to_account_info(),
-        authority: context.accounts.offer.to_account_info(),
-    };
+pub fn withdraw_and_close_vault<'info>(
+    context: &Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+    token_a_amount: u64,
+) -> Result<()> {
+    settlement(context, 0, &[]).release_token_a(token_a_amount)
+}
+
+/// 带解锁计划的报价将代币A锁定在托管账户中，收取代币A侧的手续费
+///
+/// # 参数
+/// - `context`: 指令执行上下文，包含所有必要账户
+/// - `token_a_amount`: 本次成交的代币A数量
+///
+/// # 返回值
+/// - `Result<u64>`: 锁定给接受者、之后逐步解锁的代币A数量
+pub fn lock_vesting_tokens<'info>(
+    context: &Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+    token_a_amount: u64,
+) -> Result<u64> {
+    settlement(context, 0, &[]).lock_token_a(token_a_amount)
+}
+
+/// 结算一篮子报价的额外交易腿
+///
+/// remaining_accounts最前面依次为代币A侧每条额外腿的 [Mint, 托管账户, 接受者代币账户]
+/// 和代币B侧每条额外腿的 [Mint, 接受者代币账户, 创建者代币账户]
+///
+/// # 参数
+/// - `context`: 指令执行上下文，包含所有必要账户
+///
+/// # 返回值
+/// - `Result<()>`: 操作成功返回Ok，失败返回错误信息
+pub fn settle_basket<'info>(
+    context: &Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+) -> Result<()> {
+    let offer = &context.accounts.offer;
+    if !offer.is_basket() {
+        return Ok(());
+    }
 
-    let cpi_context = CpiContext::new_with_signer(
-        context.accounts.token_program.to_account_info(),
-        accounts,
+    let seeds = offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    settle_basket_legs(
+        &context.accounts.taker,
+        &context.accounts.maker.to_account_info(),
+        &offer.to_account_info(),
+        &context.accounts.config,
         &signer_seeds,
-    );
-    transfer_checked(
-        cpi_context,
-        context.accounts.vault.amount,
-        context.accounts.token_mint_a.decimals,
-    )?;
-
-    let accounts = CloseAccount {
-        account: context.accounts.vault.to_account_info(),
-        destination: context.accounts.taker.to_account_info(),
-        authority: context.accounts.offer.to_account_info(),
+        &offer.extra_legs_a,
+        &offer.extra_legs_b,
+        context.remaining_accounts,
+        &context.accounts.token_program_a,
+        &context.accounts.token_program_b,
+        &context.accounts.associated_token_program,
+        &context.accounts.system_program,
+    )
+}
+
+/// 计算代币A为NFT时本次成交应支付给已验证创作者的版税
+///
+/// 代币A为NFT时必须提供其元数据账户，版税从接受者支付的代币B中扣除；
+/// 创作者的收款账户（代币B关联账户，代币B为原生SOL时为创作者本身）放在remaining_accounts中
+///
+/// # 参数
+/// - `context`: 指令执行上下文，包含所有必要账户
+/// - `token_b_amount`: 本次支付的代币B数量
+///
+/// # 返回值
+/// - `Result<Vec<RoyaltyPayment>>`: 每位已验证创作者的版税，代币A不是NFT时为空
+pub fn royalty_payments(
+    context: &Context<TakeOffer>,
+    token_b_amount: u64,
+) -> Result<Vec<RoyaltyPayment>> {
+    if !is_nft(&context.accounts.token_mint_a) {
+        return Ok(Vec::new());
+    }
+    let nft_metadata = context
+        .accounts
+        .nft_metadata
+        .as_ref()
+        .ok_or(ErrorCode::MissingMetadataAccount)?;
+    creator_royalties(nft_metadata, token_b_amount)
+}
+
+/// 接受者代币A账户的当前余额，代币A为原生SOL时为None
+pub fn taker_token_a_balance(context: &Context<TakeOffer>) -> Result<Option<u64>> {
+    context
+        .accounts
+        .taker_token_account_a
+        .as_ref()
+        .map(|taker_token_account_a| {
+            token_account_balance(&taker_token_account_a.to_account_info())
+        })
+        .transpose()
+}
+
+/// 接受者代币B账户的当前余额，代币B为原生SOL时为None
+pub fn taker_token_b_balance(context: &Context<TakeOffer>) -> Result<Option<u64>> {
+    context
+        .accounts
+        .taker_token_account_b
+        .as_ref()
+        .map(|taker_token_account_b| {
+            token_account_balance(&taker_token_account_b.to_account_info())
+        })
+        .transpose()
+}
+
+/// 检查接受者实际收到和支付的数量是否在其可接受的范围内
+///
+/// 代币一侧按代币账户的余额变化计算；原生SOL一侧按实际转账的lamports计算，
+/// 接受者的lamports还包含关联账户租金的支付与返还，不能用余额变化衡量
+///
+/// # 参数
+/// - `context`: 指令执行上下文，包含所有必要账户
+/// - `taker_token_a_balance_before`: 结算前接受者代币A账户余额，原生SOL时为None
+/// - `taker_token_b_balance_before`: 结算前接受者代币B账户余额，原生SOL时为None
+/// - `token_a_amount`: 本次成交释放的代币A数量
+/// - `token_b_amount`: 本次支付的代币B数量
+/// - `expected_token_a_amount`: 接受者至少要收到的代币A数量
+/// - `max_token_b_amount`: 接受者最多愿意支付的代币B数量
+/// - `token_a_vesting_amount`: 锁定给接受者逐步解锁的代币A数量，计入收到的数量
+///
+/// # 返回值
+/// - `Result<()>`: 操作成功返回Ok，失败返回错误信息
+#[allow(clippy::too_many_arguments)]
+pub fn check_slippage(
+    context: &Context<TakeOffer>,
+    taker_token_a_balance_before: Option<u64>,
+    taker_token_b_balance_before: Option<u64>,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    expected_token_a_amount: u64,
+    max_token_b_amount: u64,
+    token_a_vesting_amount: u64,
+) -> Result<()> {
+    let token_a_released = match (
+        taker_token_a_balance_before,
+        taker_token_a_balance(context)?,
+    ) {
+        (Some(before), Some(after)) => after.checked_sub(before),
+        // 原生SOL从报价账户直接转给接受者，手续费从代币A收取时先扣除
+        _ => {
+            let fee = match context.accounts.config.fee_side {
+                FeeSide::TokenA => context.accounts.config.protocol_fee(token_a_amount)?,
+                FeeSide::TokenB => 0,
+            };
+            token_a_amount.checked_sub(fee)
+        }
     };
+    let token_a_received = token_a_released
+        .and_then(|amount| amount.checked_add(token_a_vesting_amount))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_gte!(
+        token_a_received,
+        expected_token_a_amount,
+        ErrorCode::TokenAAmountTooLow
+    );
 
-    let cpi_context = CpiContext::new_with_signer(
-        context.accounts.token_program.to_account_info(),
-        accounts,
-        &signer_seeds,
+    let token_b_paid = match (
+        taker_token_b_balance_before,
+        taker_token_b_balance(context)?,
+    ) {
+        (Some(before), Some(after)) => before.checked_sub(after).ok_or(ErrorCode::MathOverflow)?,
+        // 原生SOL的手续费、版税与创建者所得合计恰好为本次支付的数量
+        _ => token_b_amount,
+    };
+    require_gte!(
+        max_token_b_amount,
+        token_b_paid,
+        ErrorCode::TokenBAmountTooHigh
     );
-    close_account(cpi_context)
+
+    Ok(())
+}
+
+/// 记录本次成交、更新创建者档案的成交统计并发出成交事件，全部成交时关闭报价账户
+///
+/// 带解锁计划的报价记录接受者与锁定数量，报价账户保留到代币A全部提取为止
+///
+/// # 参数
+/// - `context`: 指令执行上下文，包含所有必要账户
+/// - `token_a_amount`: 本次成交的代币A数量
+/// - `token_b_amount`: 本次支付的代币B数量
+/// - `now`: 当前Unix时间戳，用于荷兰拍卖的价格折算
+/// - `token_a_vesting_amount`: 锁定给接受者逐步解锁的代币A数量
+///
+/// # 返回值
+/// - `Result<()>`: 操作成功返回Ok，失败返回错误信息
+pub fn record_fill(
+    context: Context<TakeOffer>,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    now: i64,
+    token_a_vesting_amount: u64,
+) -> Result<()> {
+    context
+        .accounts
+        .offer
+        .record_fill(token_a_amount, token_b_amount, now)?;
+    let is_filled = context.accounts.offer.is_filled();
+    context.accounts.maker_profile.record_fill(is_filled)?;
+
+    let ctx = &context;
+    let taker = ctx.accounts.taker.key();
+    if is_filled {
+        emit_cpi!(OfferTaken::new(
+            &ctx.accounts.offer,
+            taker,
+            token_a_amount,
+            token_b_amount
+        ));
+    } else {
+        emit_cpi!(OfferPartiallyTaken::new(
+            &ctx.accounts.offer,
+            taker,
+            token_a_amount,
+            token_b_amount
+        ));
+    }
+
+    let offer = &mut context.accounts.offer;
+
+    if offer.vesting.is_some() {
+        offer.vesting_beneficiary = Some(context.accounts.taker.key());
+        offer.token_a_vesting_amount = token_a_vesting_amount;
+        return Ok(());
+    }
+
+    if is_filled {
+        offer.close(context.accounts.maker.to_account_info())?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, OfferPartiallyTaken, OfferTaken, SwapConfig};
+
+use super::{
+    associated_token_account, check_mint, is_native_mint, is_nft, maker_profile_account,
+    offer_account, token_account_balance, vault_account, OfferSettlement, OFFER_GROUP_ACCOUNTS,
+};
+
+/// 一次成交多个报价的账户结构
+///
+/// 所有报价必须属于同一交易对，每个报价的账户组按`token_b_fill_amounts`的顺序
+/// 放在remaining_accounts中，见`OFFER_GROUP_ACCOUNTS`
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeOffers<'info> {
+    /// 交易执行者，需提供签名
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+    /// 代币B的Mint账户
+    #[account(mint::token_program = token_program_b)]
+    pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// 交易执行者代币A的关联账户（如果不存在则自动创建）
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 交易执行者代币B的关联账户
+    #[account(
+        mut,
+        associated_token::mint = token_mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 全局配置账户（协议暂停时不能成交）
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub config: Account<'info, SwapConfig>,
+
+    /// 手续费接收者的代币账户（代币A或代币B，取决于配置的收费方向；
+    /// 不收取手续费时可不传）
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_recipient @ ErrorCode::WrongFeeAccount,
+        constraint = fee_token_account.mint
+            == config.fee_mint(&token_mint_a.key(), &token_mint_b.key()) @ ErrorCode::WrongFeeAccount
+    )]
+    pub fee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 系统程序账户
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序账户
+    pub token_program_a: Interface<'info, TokenInterface>,
+    /// 代币B的代币程序账户
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// 关联代币程序账户
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// 在一笔交易中依次成交多个报价
+///
+/// 任一报价无法成交则整笔交易失败；不支持一篮子报价、带解锁计划的报价、原生SOL、NFT
+/// 和默克尔名单限制的报价（接受者证明只能通过take_offer提交）。
+/// 全部成交后按接受者实际收到和支付的总量检查滑点
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `token_b_fill_amounts` - 每个报价本次支付的代币B数量
+/// * `min_token_a_amount` - 接受者总共至少要收到的代币A数量
+/// * `max_token_b_amount` - 接受者总共最多愿意支付的代币B数量
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn take_offers<'info>(
+    context: Context<'_, '_, 'info, 'info, TakeOffers<'info>>,
+    token_b_fill_amounts: Vec<u64>,
+    min_token_a_amount: u64,
+    max_token_b_amount: u64,
+) -> Result<()> {
+    require!(
+        context.remaining_accounts.len() >= token_b_fill_amounts.len() * OFFER_GROUP_ACCOUNTS,
+        ErrorCode::MissingOfferAccounts
+    );
+    require!(
+        !is_native_mint(&context.accounts.token_mint_a.key())
+            && !is_native_mint(&context.accounts.token_mint_b.key()),
+        ErrorCode::NativeMintNotSupported
+    );
+    require!(
+        !is_nft(&context.accounts.token_mint_a),
+        ErrorCode::NftOfferNotRoutable
+    );
+    check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+    check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let taker_token_a_balance_before =
+        token_account_balance(&context.accounts.taker_token_account_a.to_account_info())?;
+    let taker_token_b_balance_before =
+        token_account_balance(&context.accounts.taker_token_account_b.to_account_info())?;
+
+    for (group, &token_b_fill_amount) in context
+        .remaining_accounts
+        .chunks_exact(OFFER_GROUP_ACCOUNTS)
+        .zip(&token_b_fill_amounts)
+    {
+        let mut offer = offer_account(&group[0])?;
+        require!(!offer.is_cancelled, ErrorCode::OfferAlreadyCancelled);
+        require!(!offer.is_expired(now), ErrorCode::OfferExpired);
+        require!(
+            !offer.is_basket() && offer.vesting.is_none() && offer.taker_merkle_root.is_none(),
+            ErrorCode::OfferNotRoutable
+        );
+        require!(!offer.is_arbitrated(), ErrorCode::OfferIsArbitrated);
+        require_keys_eq!(
+            offer.token_mint_a,
+            context.accounts.token_mint_a.key(),
+            ErrorCode::WrongTokenMint
+        );
+        require_keys_eq!(
+            offer.token_mint_b,
+            context.accounts.token_mint_b.key(),
+            ErrorCode::WrongTokenMint
+        );
+        require_keys_eq!(*group[1].key, offer.maker, ErrorCode::NotMaker);
+        let mut maker_profile = maker_profile_account(&group[4], &group[1])?;
+        offer.check_taker(&context.accounts.taker.key(), &[])?;
+
+        let token_a_amount = offer.token_a_for_fill(token_b_fill_amount, now)?;
+
+        let vault = vault_account(
+            &group[2],
+            &group[0],
+            &context.accounts.token_mint_a,
+            &context.accounts.token_program_a,
+        )?;
+        let maker_token_account_b = associated_token_account(
+            &group[3],
+            &group[1],
+            &context.accounts.token_mint_b,
+            &context.accounts.taker.to_account_info(),
+            &context.accounts.token_program_b,
+            &context.accounts.associated_token_program,
+            &context.accounts.system_program,
+        )?;
+
+        let settlement = OfferSettlement {
+            taker: context.accounts.taker.as_ref(),
+            maker: &group[1],
+            offer: &offer,
+            vault: Some(&vault),
+            token_mint_a: &context.accounts.token_mint_a,
+            token_mint_b: &context.accounts.token_mint_b,
+            taker_token_account_a: Some(&context.accounts.taker_token_account_a),
+            taker_token_account_b: Some(&context.accounts.taker_token_account_b),
+            maker_token_account_b: Some(&maker_token_account_b),
+            config: &context.accounts.config,
+            fee_token_account: context.accounts.fee_token_account.as_deref(),
+            fee_recipient: None,
+            referrer_token_account: None,
+            referral_bps: 0,
+            royalties: &[],
+            token_program_a: &context.accounts.token_program_a,
+            token_program_b: &context.accounts.token_program_b,
+            system_program: context.accounts.system_program.as_ref(),
+            remaining_accounts: context.remaining_accounts,
+        };
+        settlement.pay_maker(token_b_fill_amount)?;
+        settlement.release_token_a(token_a_amount)?;
+
+        offer.record_fill(token_a_amount, token_b_fill_amount, now)?;
+        maker_profile.record_fill(offer.is_filled())?;
+        maker_profile.exit(&crate::ID)?;
+
+        let ctx = &context;
+        let taker = ctx.accounts.taker.key();
+        if offer.is_filled() {
+            emit_cpi!(OfferTaken::new(
+                &offer,
+                taker,
+                token_a_amount,
+                token_b_fill_amount
+            ));
+        } else {
+            emit_cpi!(OfferPartiallyTaken::new(
+                &offer,
+                taker,
+                token_a_amount,
+                token_b_fill_amount
+            ));
+        }
+        if offer.is_filled() {
+            offer.close(group[1].clone())?;
+        } else {
+            offer.exit(&crate::ID)?;
+        }
+    }
+
+    let token_a_received =
+        token_account_balance(&context.accounts.taker_token_account_a.to_account_info())?
+            .checked_sub(taker_token_a_balance_before)
+            .ok_or(ErrorCode::MathOverflow)?;
+    require_gte!(
+        token_a_received,
+        min_token_a_amount,
+        ErrorCode::TokenAAmountTooLow
+    );
+
+    let token_b_paid = taker_token_b_balance_before
+        .checked_sub(token_account_balance(
+            &context.accounts.taker_token_account_b.to_account_info(),
+        )?)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_gte!(
+        max_token_b_amount,
+        token_b_paid,
+        ErrorCode::TokenBAmountTooHigh
+    );
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Market, Offer};
+
+/// 将报价从交易对市场下架的账户结构
+#[derive(Accounts)]
+pub struct UnlistOffer<'info> {
+    /// 报价创建者（必须是签名者）
+    pub maker: Signer<'info>,
+
+    /// 报价状态账户
+    #[account(
+        has_one = maker @ ErrorCode::NotMaker,
+        seeds = [b"offer", maker.key().as_ref(), offer.offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 报价交易对所属的市场账户
+    #[account(
+        mut,
+        seeds = [b"market", offer.token_mint_a.as_ref(), offer.token_mint_b.as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// 将报价从市场下架
+///
+/// # 参数
+/// * `context` - 指令上下文
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn unlist_offer(context: Context<UnlistOffer>) -> Result<()> {
+    let offer_key = context.accounts.offer.key();
+    context.accounts.market.remove(&offer_key)
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, FeeSide, SwapConfig, MAX_FEE_BPS};
+
+/// 更新全局配置的账户结构
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// 当前管理员（必须是签名者）
+    pub admin: Signer<'info>,
+
+    /// 全局配置账户
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::NotAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SwapConfig>,
+}
+
+/// 更新全局配置
+///
+/// 仅更新传入值为Some的字段
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `new_admin` - 新管理员
+/// * `fee_bps` - 新的协议手续费（基点）
+/// * `fee_recipient` - 新的手续费接收者
+/// * `fee_side` - 新的手续费收取方向
+/// * `max_referral_bps` - 新的前端推荐费率上限（基点）
+/// * `paused` - 是否暂停创建和成交报价
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn update_config(
+    context: Context<UpdateConfig>,
+    new_admin: Option<Pubkey>,
+    fee_bps: Option<u16>,
+    fee_recipient: Option<Pubkey>,
+    fee_side: Option<FeeSide>,
+    max_referral_bps: Option<u16>,
+    paused: Option<bool>,
+) -> Result<()> {
+    let config = &mut context.accounts.config;
+
+    if let Some(new_admin) = new_admin {
+        config.admin = new_admin;
+    }
+    if let Some(fee_bps) = fee_bps {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeeBps);
+        config.fee_bps = fee_bps;
+    }
+    if let Some(fee_recipient) = fee_recipient {
+        config.fee_recipient = fee_recipient;
+    }
+    if let Some(fee_side) = fee_side {
+        config.fee_side = fee_side;
+    }
+    if let Some(max_referral_bps) = max_referral_bps {
+        require!(max_referral_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeeBps);
+        config.max_referral_bps = max_referral_bps;
+    }
+    if let Some(paused) = paused {
+        config.paused = paused;
+    }
+
+    Ok(())
+}
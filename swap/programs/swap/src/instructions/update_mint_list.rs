@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, MintListMode, MAX_MINT_LIST_LEN};
+
+use super::UpdateConfig;
+
+/// 替换全局代币名单
+///
+/// 白名单模式下只允许名单中的代币，且名单中的代币跳过危险扩展检查；
+/// 黑名单模式下禁止名单中的代币
+///
+/// # 参数
+/// * `context` - 指令上下文（与更新全局配置相同的账户）
+/// * `mint_list_mode` - 名单模式
+/// * `mint_list` - 新的代币名单
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+pub fn update_mint_list(
+    context: Context<UpdateConfig>,
+    mint_list_mode: MintListMode,
+    mint_list: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        mint_list.len() <= MAX_MINT_LIST_LEN,
+        ErrorCode::MintListTooLong
+    );
+
+    let config = &mut context.accounts.config;
+    config.mint_list_mode = mint_list_mode;
+    config.mint_list = mint_list;
+    Ok(())
+}
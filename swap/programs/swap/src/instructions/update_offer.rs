@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{error::ErrorCode, Market, Offer, OfferUpdated};
+
+use super::{
+    is_native_mint, token_account_balance, transfer_lamports, transfer_lamports_from_pda,
+    transfer_tokens,
+};
+
+/// 修改报价的账户结构
+///
+/// 允许报价创建者在不撤销报价的情况下调整价格、追加或部分取回托管的代币
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct UpdateOffer<'info> {
+    /// 报价创建者（必须是签名者）
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// 代币A的Mint账户
+    #[account(mint::token_program = token_program_a)]
+    pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+    /// 报价创建者的代币A账户（追加代币的来源、取回代币的去向，代币A为原生SOL时不传）
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_token_account_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 报价状态账户
+    #[account(
+        mut,
+        has_one = maker @ ErrorCode::NotMaker,
+        has_one = token_mint_a @ ErrorCode::WrongTokenMint,
+        seeds = [b"offer", maker.key().as_ref(), offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump,
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// 代币A的托管账户（代币A为原生SOL时不传）
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// 报价交易对所属的市场账户（报价已上架时传入，修改后按新价格重新排序）
+    #[account(
+        mut,
+        seeds = [b"market", offer.token_mint_a.as_ref(), offer.token_mint_b.as_ref()],
+        bump = market.bump
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
+
+    /// 系统程序（代币A为原生SOL时用于追加lamports）
+    pub system_program: Program<'info, System>,
+    /// 代币A的代币程序接口
+    pub token_program_a: Interface<'info, TokenInterface>,
+}
+
+/// 修改报价主函数
+///
+/// 依次处理追加托管、部分取回和重新定价，并将报价修订号加一，
+/// 使按旧修订号报价的接受者交易失败；传入市场账户时同步更新已上架报价的记录
+///
+/// # 参数
+/// * `context` - 指令上下文，包含所有相关账户
+/// * `new_token_b_remaining_amount` - 新的剩余代币B数量，None表示不修改价格
+/// * `token_a_deposit_amount` - 追加存入托管账户的代币A数量
+/// * `token_a_withdraw_amount` - 从托管账户取回的代币A数量
+///
+/// # 返回值
+/// * `Result<()>` - 成功返回Ok，失败返回错误信息
+pub fn update_offer<'info>(
+    context: Context<'_, '_, 'info, 'info, UpdateOffer<'info>>,
+    new_token_b_remaining_amount: Option<u64>,
+    token_a_deposit_amount: u64,
+    token_a_withdraw_amount: u64,
+) -> Result<()> {
+    require!(
+        !context.accounts.offer.is_cancelled,
+        ErrorCode::OfferAlreadyCancelled
+    );
+    // 已成交的解锁报价中托管的代币A归属于接受者
+    require!(
+        !context.accounts.offer.is_vesting(),
+        ErrorCode::OfferIsVesting
+    );
+    // 仲裁报价的托管代币只能由仲裁人或双方共同处置
+    require!(
+        !context.accounts.offer.is_arbitrated(),
+        ErrorCode::OfferIsArbitrated
+    );
+    require!(
+        !context
+            .accounts
+            .offer
+            .is_expired(Clock::get()?.unix_timestamp),
+        ErrorCode::OfferExpired
+    );
+
+    let token_a_received = if token_a_deposit_amount > 0 {
+        deposit_to_vault(&context, token_a_deposit_amount)?
+    } else {
+        0
+    };
+
+    if token_a_withdraw_amount > 0 {
+        // 取回后必须仍有剩余代币A，全部取回请使用cancel_offer
+        let token_a_available_amount = context
+            .accounts
+            .offer
+            .token_a_remaining_amount
+            .checked_add(token_a_received)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            token_a_withdraw_amount < token_a_available_amount,
+            ErrorCode::InvalidAmount
+        );
+        withdraw_from_vault(&context, token_a_withdraw_amount)?;
+    }
+
+    let offer = &mut context.accounts.offer;
+    offer.token_a_offered_amount = offer
+        .token_a_offered_amount
+        .checked_add(token_a_received)
+        .and_then(|amount| amount.checked_sub(token_a_withdraw_amount))
+        .ok_or(ErrorCode::MathOverflow)?;
+    offer.token_a_remaining_amount = offer
+        .token_a_remaining_amount
+        .checked_add(token_a_received)
+        .and_then(|amount| amount.checked_sub(token_a_withdraw_amount))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    if let Some(new_token_b_remaining_amount) = new_token_b_remaining_amount {
+        require!(new_token_b_remaining_amount > 0, ErrorCode::InvalidAmount);
+
+        let token_b_filled_amount = offer.token_b_wanted_amount - offer.token_b_remaining_amount;
+        offer.token_b_wanted_amount = token_b_filled_amount
+            .checked_add(new_token_b_remaining_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        offer.token_b_remaining_amount = new_token_b_remaining_amount;
+
+        // 荷兰拍卖的起始价随之变化，底价必须仍低于起始价
+        if let Some(dutch_auction) = offer.dutch_auction {
+            dutch_auction.validate(offer.token_b_wanted_amount)?;
+        }
+    }
+
+    offer.revision = offer
+        .revision
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let offer_key = offer.key();
+    let offer = &context.accounts.offer;
+    if let Some(market) = context.accounts.market.as_mut() {
+        market.refresh(offer_key, offer)?;
+    }
+
+    let ctx = &context;
+    emit_cpi!(OfferUpdated::new(&ctx.accounts.offer));
+    Ok(())
+}
+
+/// 向托管账户追加代币A
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `amount` - 追加的代币A数量
+///
+/// # 返回值
+/// * `Result<u64>` - 托管账户实际到账的数量（已扣除转账手续费）
+fn deposit_to_vault<'info>(
+    context: &Context<'_, '_, 'info, 'info, UpdateOffer<'info>>,
+    amount: u64,
+) -> Result<u64> {
+    if is_native_mint(&context.accounts.token_mint_a.key()) {
+        transfer_lamports(
+            &context.accounts.maker.to_account_info(),
+            &context.accounts.offer.to_account_info(),
+            amount,
+            &context.accounts.system_program.to_account_info(),
+        )?;
+        return Ok(amount);
+    }
+
+    let vault = context
+        .accounts
+        .vault
+        .as_ref()
+        .ok_or(ErrorCode::MissingTokenAccount)?;
+    let vault_balance_before = token_account_balance(&vault.to_account_info())?;
+
+    transfer_tokens(
+        context
+            .accounts
+            .maker_token_account_a
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
+        vault,
+        &amount,
+        &context.accounts.token_mint_a,
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.token_program_a,
+        &[],
+        context.remaining_accounts,
+    )?;
+
+    let vault_balance_after = token_account_balance(&vault.to_account_info())?;
+    Ok(vault_balance_after - vault_balance_before)
+}
+
+/// 从托管账户取回部分代币A给报价创建者
+///
+/// # 参数
+/// * `context` - 指令上下文
+/// * `amount` - 取回的代币A数量
+///
+/// # 返回值
+/// * `Result<()>` - 操作结果
+fn withdraw_from_vault<'info>(
+    context: &Context<'_, '_, 'info, 'info, UpdateOffer<'info>>,
+    amount: u64,
+) -> Result<()> {
+    if is_native_mint(&context.accounts.token_mint_a.key()) {
+        return transfer_lamports_from_pda(
+            &context.accounts.offer.to_account_info(),
+            &context.accounts.maker.to_account_info(),
+            amount,
+        );
+    }
+
+    let seeds = context.accounts.offer.signer_seeds();
+    let signer_seeds = [&seeds.as_seeds()[..]];
+
+    transfer_tokens(
+        context
+            .accounts
+            .vault
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
+        context
+            .accounts
+            .maker_token_account_a
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccount)?,
+        &amount,
+        &context.accounts.token_mint_a,
+        &context.accounts.offer.to_account_info(),
+        &context.accounts.token_program_a,
+        &signer_seeds,
+        context.remaining_accounts,
+    )
+}
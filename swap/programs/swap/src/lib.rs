@@ -1,13 +1,18 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
+#![allow(ambiguous_glob_reexports)]
+// #[program]为cpi特性生成的同名调用函数沿用指令参数，无法单独标注
+#![allow(clippy::too_many_arguments)]
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
 pub mod state;
 
 use anchor_lang::prelude::*;
 
 pub use constants::*;
+pub use events::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -17,25 +22,322 @@ declare_id!("GkAaGQj9ETzMtYsYgdkfJxXUDTZQ7ZEQQcujci4T27y9");
 pub mod swap {
     use super::*;
 
-    pub fn make_offer(
-        ctx: Context<MakeOffer>,
-        id: u64,
+    pub fn make_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeOffer<'info>>,
         token_a_offered_amount: u64,
         token_b_wanted_amount: u64,
+        expires_at: Option<i64>,
+        allowed_taker: Option<Pubkey>,
+        taker_merkle_root: Option<[u8; 32]>,
+        extra_legs_a: Vec<OfferLeg>,
+        extra_legs_b: Vec<OfferLeg>,
+        dutch_auction: Option<DutchAuction>,
+        vesting: Option<VestingSchedule>,
+        arbiter: Option<Pubkey>,
+        dispute_deadline: Option<i64>,
     ) -> Result<()> {
+        let mut extra_legs_a = extra_legs_a;
+        instructions::make_offer::check_offer_mints(&ctx)?;
         instructions::make_offer::send_offered_tokens_to_vault(&ctx, token_a_offered_amount)?;
-        instructions::make_offer::save_offer(ctx, id, token_b_wanted_amount)
+        instructions::make_offer::send_basket_tokens_to_vaults(
+            &ctx,
+            &mut extra_legs_a,
+            &extra_legs_b,
+        )?;
+        instructions::make_offer::save_offer(
+            ctx,
+            token_b_wanted_amount,
+            expires_at,
+            allowed_taker,
+            taker_merkle_root,
+            extra_legs_a,
+            extra_legs_b,
+            dutch_auction,
+            vesting,
+            arbiter,
+            dispute_deadline,
+        )
     }
 
-    pub fn take_offer(context: Context<TakeOffer>) -> Result<()> {
+    pub fn take_offer<'info>(
+        context: Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+        token_b_fill_amount: u64,
+        taker_proof: Vec<[u8; 32]>,
+        expected_revision: u32,
+        expected_token_a_amount: u64,
+        max_token_b_amount: u64,
+        referral_bps: u16,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
         // 检查报价是否已被取消
-        require!(!context.accounts.offer.is_cancelled, crate::error::ErrorCode::OfferAlreadyCancelled);
-        
-        instructions::take_offer::send_wanted_tokens_to_maker(&context)?;
-        instructions::take_offer::withdraw_and_close_vault(&context)
+        require!(
+            !context.accounts.offer.is_cancelled,
+            crate::error::ErrorCode::OfferAlreadyCancelled
+        );
+        // 检查报价是否已过期
+        require!(
+            !context.accounts.offer.is_expired(now),
+            crate::error::ErrorCode::OfferExpired
+        );
+        // 仲裁报价的代币B在链下结算，不能直接成交
+        require!(
+            !context.accounts.offer.is_arbitrated(),
+            crate::error::ErrorCode::OfferIsArbitrated
+        );
+        // 检查代币是否仍被全局配置允许
+        instructions::check_mint(&context.accounts.config, &context.accounts.token_mint_a)?;
+        instructions::check_mint(&context.accounts.config, &context.accounts.token_mint_b)?;
+        // 检查报价在接受者报价之后是否被创建者修改过
+        require_eq!(
+            context.accounts.offer.revision,
+            expected_revision,
+            crate::error::ErrorCode::OfferRevisionMismatch
+        );
+        // 检查接受者是否在报价允许的范围内
+        context
+            .accounts
+            .offer
+            .check_taker(&context.accounts.taker.key(), &taker_proof)?;
+
+        // 按当前价格下的剩余比例计算本次成交释放的代币A数量
+        let token_a_amount = context
+            .accounts
+            .offer
+            .token_a_for_fill(token_b_fill_amount, now)?;
+
+        // 记录接受者结算前的余额，用于滑点检查
+        let taker_token_a_balance_before =
+            instructions::take_offer::taker_token_a_balance(&context)?;
+        let taker_token_b_balance_before =
+            instructions::take_offer::taker_token_b_balance(&context)?;
+
+        // 代币A为NFT时从代币B中向已验证创作者支付版税
+        let royalties = instructions::take_offer::royalty_payments(&context, token_b_fill_amount)?;
+
+        instructions::take_offer::send_wanted_tokens_to_maker(
+            &context,
+            token_b_fill_amount,
+            referral_bps,
+            &royalties,
+        )?;
+        // 带解锁计划的报价将代币A留在托管账户中，之后由接受者逐步提取
+        let token_a_vesting_amount = if context.accounts.offer.vesting.is_some() {
+            instructions::take_offer::lock_vesting_tokens(&context, token_a_amount)?
+        } else {
+            instructions::take_offer::withdraw_and_close_vault(&context, token_a_amount)?;
+            0
+        };
+        instructions::take_offer::settle_basket(&context)?;
+
+        // 按实际到账和实际支付的数量（含协议费和转账手续费）检查滑点
+        instructions::take_offer::check_slippage(
+            &context,
+            taker_token_a_balance_before,
+            taker_token_b_balance_before,
+            token_a_amount,
+            token_b_fill_amount,
+            expected_token_a_amount,
+            max_token_b_amount,
+            token_a_vesting_amount,
+        )?;
+        instructions::take_offer::record_fill(
+            context,
+            token_a_amount,
+            token_b_fill_amount,
+            now,
+            token_a_vesting_amount,
+        )
     }
 
-    pub fn cancel_offer(ctx: Context<CancelOffer>, _offer_id: u64) -> Result<()> {
+    pub fn cancel_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelOffer<'info>>,
+        _offer_id: u64,
+    ) -> Result<()> {
         instructions::cancel_offer::cancel_offer(ctx)
     }
+
+    pub fn update_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateOffer<'info>>,
+        _offer_id: u64,
+        new_token_b_remaining_amount: Option<u64>,
+        token_a_deposit_amount: u64,
+        token_a_withdraw_amount: u64,
+    ) -> Result<()> {
+        instructions::update_offer::update_offer(
+            ctx,
+            new_token_b_remaining_amount,
+            token_a_deposit_amount,
+            token_a_withdraw_amount,
+        )
+    }
+
+    pub fn reclaim_expired_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimExpiredOffer<'info>>,
+    ) -> Result<()> {
+        instructions::reclaim_expired_offer::reclaim_expired_offer(ctx)
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        fee_side: FeeSide,
+        max_referral_bps: u16,
+    ) -> Result<()> {
+        instructions::initialize_config::initialize_config(
+            ctx,
+            fee_bps,
+            fee_recipient,
+            fee_side,
+            max_referral_bps,
+        )
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        new_admin: Option<Pubkey>,
+        fee_bps: Option<u16>,
+        fee_recipient: Option<Pubkey>,
+        fee_side: Option<FeeSide>,
+        max_referral_bps: Option<u16>,
+        paused: Option<bool>,
+    ) -> Result<()> {
+        instructions::update_config::update_config(
+            ctx,
+            new_admin,
+            fee_bps,
+            fee_recipient,
+            fee_side,
+            max_referral_bps,
+            paused,
+        )
+    }
+
+    pub fn update_mint_list(
+        ctx: Context<UpdateConfig>,
+        mint_list_mode: MintListMode,
+        mint_list: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::update_mint_list::update_mint_list(ctx, mint_list_mode, mint_list)
+    }
+
+    pub fn create_market(ctx: Context<CreateMarket>) -> Result<()> {
+        instructions::create_market::create_market(ctx)
+    }
+
+    pub fn list_offer(ctx: Context<ListOffer>) -> Result<()> {
+        instructions::list_offer::list_offer(ctx)
+    }
+
+    pub fn unlist_offer(ctx: Context<UnlistOffer>) -> Result<()> {
+        instructions::unlist_offer::unlist_offer(ctx)
+    }
+
+    pub fn prune_market<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PruneMarket<'info>>,
+    ) -> Result<()> {
+        instructions::prune_market::prune_market(ctx)
+    }
+
+    pub fn take_best<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeBest<'info>>,
+        token_b_budget: u64,
+        min_token_a_amount: u64,
+        offer_count: u8,
+    ) -> Result<()> {
+        instructions::take_best::take_best(ctx, token_b_budget, min_token_a_amount, offer_count)
+    }
+
+    pub fn take_offers<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeOffers<'info>>,
+        token_b_fill_amounts: Vec<u64>,
+        min_token_a_amount: u64,
+        max_token_b_amount: u64,
+    ) -> Result<()> {
+        instructions::take_offers::take_offers(
+            ctx,
+            token_b_fill_amounts,
+            min_token_a_amount,
+            max_token_b_amount,
+        )
+    }
+
+    pub fn fill_signed_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FillSignedOrder<'info>>,
+        order: SignedOrder,
+    ) -> Result<()> {
+        instructions::fill_signed_order::fill_signed_order(ctx, order)
+    }
+
+    pub fn cancel_order_nonce(
+        ctx: Context<CancelOrderNonce>,
+        order_nonce: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::cancel_order_nonce::cancel_order_nonce(ctx, order_nonce, expires_at)
+    }
+
+    pub fn close_order_nonce(ctx: Context<CloseOrderNonce>) -> Result<()> {
+        instructions::close_order_nonce::close_order_nonce(ctx)
+    }
+
+    pub fn claim_vested<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimVested<'info>>,
+    ) -> Result<()> {
+        instructions::claim_vested::claim_vested(ctx)
+    }
+
+    pub fn release_to_taker<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseToTaker<'info>>,
+    ) -> Result<()> {
+        instructions::release_to_taker::release_to_taker(ctx)
+    }
+
+    pub fn refund_to_maker<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundToMaker<'info>>,
+    ) -> Result<()> {
+        instructions::refund_to_maker::refund_to_maker(ctx)
+    }
+
+    pub fn init_pool(ctx: Context<InitPool>, fee_bps: u16) -> Result<()> {
+        instructions::init_pool::init_pool(ctx, fee_bps)
+    }
+
+    pub fn add_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
+        max_token_a_amount: u64,
+        max_token_b_amount: u64,
+        min_lp_amount: u64,
+    ) -> Result<()> {
+        instructions::add_liquidity::add_liquidity(
+            ctx,
+            max_token_a_amount,
+            max_token_b_amount,
+            min_lp_amount,
+        )
+    }
+
+    pub fn remove_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
+        lp_amount: u64,
+        min_token_a_amount: u64,
+        min_token_b_amount: u64,
+    ) -> Result<()> {
+        instructions::remove_liquidity::remove_liquidity(
+            ctx,
+            lp_amount,
+            min_token_a_amount,
+            min_token_b_amount,
+        )
+    }
+
+    pub fn swap_exact_in<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapExactIn<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        a_to_b: bool,
+    ) -> Result<()> {
+        instructions::swap_exact_in::swap_exact_in(ctx, amount_in, min_amount_out, a_to_b)
+    }
 }
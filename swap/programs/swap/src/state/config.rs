@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, BPS_DENOMINATOR, MAX_MINT_LIST_LEN};
+
+/// 协议手续费的收取方向
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum FeeSide {
+    /// 从接受者释放的代币A中扣除
+    TokenA,
+    /// 从接受者支付的代币B中扣除
+    TokenB,
+}
+
+/// 全局代币名单的模式
+///
+/// 不在白名单中的代币不得设置冻结权限，USDC、USDT等主流稳定币都保留了冻结权限，
+/// 因此在未启用名单或黑名单模式下无法使用；需要支持这类代币时应启用白名单并将其加入名单
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum MintListMode {
+    /// 不启用名单
+    Disabled,
+    /// 只允许名单中的代币
+    Allowlist,
+    /// 禁止名单中的代币
+    Denylist,
+}
+
+/// 全局配置结构体
+///
+/// 存储协议管理员及手续费设置，所有报价共享同一个配置账户
+#[account]
+#[derive(InitSpace)]
+pub struct SwapConfig {
+    /// 管理员公钥（可更新配置）
+    pub admin: Pubkey,
+    /// 协议手续费，单位为基点
+    pub fee_bps: u16,
+    /// 手续费接收者公钥
+    pub fee_recipient: Pubkey,
+    /// 手续费的收取方向
+    pub fee_side: FeeSide,
+    /// 前端推荐费率上限，单位为基点
+    pub max_referral_bps: u16,
+    /// 是否暂停创建和成交报价（含仲裁报价的放款与退款；撤销与回收过期报价不受影响）
+    pub paused: bool,
+    /// 代币名单的模式
+    pub mint_list_mode: MintListMode,
+    /// 代币名单
+    #[max_len(MAX_MINT_LIST_LEN)]
+    pub mint_list: Vec<Pubkey>,
+    /// PDA账户的bump种子
+    pub bump: u8,
+}
+
+impl SwapConfig {
+    /// 计算给定成交数量应收取的协议手续费（向下取整）
+    pub fn protocol_fee(&self, amount: u64) -> Result<u64> {
+        bps_of(amount, self.fee_bps)
+    }
+
+    /// 计算给定成交数量应支付给推荐者的费用（向下取整），费率不得超过配置上限
+    pub fn referral_fee(&self, amount: u64, referral_bps: u16) -> Result<u64> {
+        require!(
+            referral_bps <= self.max_referral_bps,
+            ErrorCode::ReferralBpsTooHigh
+        );
+        bps_of(amount, referral_bps)
+    }
+
+    /// 按代币名单检查代币是否允许使用
+    ///
+    /// 返回代币是否在白名单中，白名单中的代币由管理员认可，跳过危险扩展检查
+    pub fn check_mint_list(&self, mint: &Pubkey) -> Result<bool> {
+        let listed = self.mint_list.contains(mint);
+        match self.mint_list_mode {
+            MintListMode::Disabled => Ok(false),
+            MintListMode::Allowlist => {
+                require!(listed, ErrorCode::MintNotAllowed);
+                Ok(true)
+            }
+            MintListMode::Denylist => {
+                require!(!listed, ErrorCode::MintNotAllowed);
+                Ok(false)
+            }
+        }
+    }
+
+    /// 手续费账户应对应的代币Mint地址
+    pub fn fee_mint(&self, token_mint_a: &Pubkey, token_mint_b: &Pubkey) -> Pubkey {
+        match self.fee_side {
+            FeeSide::TokenA => *token_mint_a,
+            FeeSide::TokenB => *token_mint_b,
+        }
+    }
+}
+
+/// 按基点计算数量的比例部分（向下取整）
+pub(crate) fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let part = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok(part as u64)
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// 报价创建者档案
+///
+/// 以创建者为种子的PDA，为创建者的报价分配递增的报价ID，
+/// 并记录未关闭的报价数量与累计成交统计（可作为创建者信誉展示）
+#[account]
+#[derive(InitSpace)]
+pub struct MakerProfile {
+    /// 报价创建者公钥
+    pub maker: Pubkey,
+    /// 下一个报价的ID，报价ID从0开始递增
+    pub next_offer_id: u64,
+    /// 尚未成交、撤销或回收的报价数量
+    pub open_offer_count: u64,
+    /// 累计成交次数（部分成交每次计一次）
+    pub fill_count: u64,
+    /// 累计全部成交的报价数量
+    pub filled_offer_count: u64,
+    /// PDA账户的bump种子
+    pub bump: u8,
+}
+
+impl MakerProfile {
+    /// 为新报价分配ID并计入未关闭的报价数量
+    pub fn open_offer(&mut self) -> Result<u64> {
+        let offer_id = self.next_offer_id;
+        self.next_offer_id = offer_id.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.open_offer_count = self
+            .open_offer_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(offer_id)
+    }
+
+    /// 记录一次成交，报价全部成交时不再计入未关闭的报价数量
+    pub fn record_fill(&mut self, offer_filled: bool) -> Result<()> {
+        self.fill_count = self
+            .fill_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if offer_filled {
+            self.filled_offer_count = self
+                .filled_offer_count
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.close_offer();
+        }
+        Ok(())
+    }
+
+    /// 报价被撤销、回收或全部成交后不再计入未关闭的报价数量
+    pub fn close_offer(&mut self) {
+        self.open_offer_count = self.open_offer_count.saturating_sub(1);
+    }
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Offer, MAX_MARKET_OFFERS};
+
+/// 市场索引中的一条报价记录
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct MarketEntry {
+    /// 报价账户地址
+    pub offer: Pubkey,
+    /// 上架时的报价修订号，报价被修改后记录失效
+    pub revision: u32,
+    /// 上架时剩余的代币A数量
+    pub token_a_amount: u64,
+    /// 上架时剩余的代币B数量
+    pub token_b_amount: u64,
+}
+
+impl MarketEntry {
+    /// 按单位代币A的代币B价格比较，价格低者在前
+    fn is_cheaper_than(&self, other: &MarketEntry) -> bool {
+        (self.token_b_amount as u128) * (other.token_a_amount as u128)
+            < (other.token_b_amount as u128) * (self.token_a_amount as u128)
+    }
+}
+
+/// 交易对市场结构体
+///
+/// 以(代币A, 代币B)为种子的PDA，按价格从低到高维护出售代币A换取代币B的报价索引
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    /// 代币A的Mint地址（报价出售的代币）
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址（报价想要的代币）
+    pub token_mint_b: Pubkey,
+    /// 按价格升序排列的报价，同价格按上架先后排列
+    #[max_len(MAX_MARKET_OFFERS)]
+    pub offers: Vec<MarketEntry>,
+    /// PDA账户的bump种子
+    pub bump: u8,
+}
+
+impl Market {
+    /// 按报价当前剩余数量上架，插入到同价格报价之后
+    ///
+    /// 市场已满时，新报价比价格最高的报价更便宜则挤出后者，否则拒绝上架
+    pub fn insert(&mut self, offer_key: Pubkey, offer: &Offer) -> Result<()> {
+        require!(
+            self.position(&offer_key).is_none(),
+            ErrorCode::OfferAlreadyListed
+        );
+
+        let entry = MarketEntry {
+            offer: offer_key,
+            revision: offer.revision,
+            token_a_amount: offer.token_a_remaining_amount,
+            token_b_amount: offer.token_b_remaining_amount,
+        };
+        if self.offers.len() >= MAX_MARKET_OFFERS {
+            let worst = self.offers.last().ok_or(ErrorCode::MarketFull)?;
+            require!(entry.is_cheaper_than(worst), ErrorCode::MarketFull);
+            self.offers.pop();
+        }
+        let index = self
+            .offers
+            .iter()
+            .position(|other| entry.is_cheaper_than(other))
+            .unwrap_or(self.offers.len());
+        self.offers.insert(index, entry);
+        Ok(())
+    }
+
+    /// 报价被修改后按新的剩余数量和修订号重新排序，未上架的报价不受影响
+    pub fn refresh(&mut self, offer_key: Pubkey, offer: &Offer) -> Result<()> {
+        if self.position(&offer_key).is_none() {
+            return Ok(());
+        }
+        self.remove(&offer_key)?;
+        self.insert(offer_key, offer)
+    }
+
+    /// 下架指定报价
+    pub fn remove(&mut self, offer_key: &Pubkey) -> Result<()> {
+        let index = self.position(offer_key).ok_or(ErrorCode::OfferNotListed)?;
+        self.offers.remove(index);
+        Ok(())
+    }
+
+    /// 查找报价在索引中的位置
+    pub fn position(&self, offer_key: &Pubkey) -> Option<usize> {
+        self.offers
+            .iter()
+            .position(|entry| entry.offer == *offer_key)
+    }
+}
@@ -1,2 +1,12 @@
+pub mod config;
+pub mod maker_profile;
+pub mod market;
 pub mod offer;
+pub mod pool;
+pub mod signed_order;
+pub use config::*;
+pub use maker_profile::*;
+pub use market::*;
 pub use offer::*;
+pub use pool::*;
+pub use signed_order::*;
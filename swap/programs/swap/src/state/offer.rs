@@ -1,7 +1,108 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+use crate::{error::ErrorCode, MAX_BASKET_LEGS};
+
+/// 一篮子报价中的一条额外交易腿
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct OfferLeg {
+    /// 该腿代币的Mint地址
+    pub mint: Pubkey,
+    /// 该腿的代币数量（代币A侧为实际托管数量）
+    pub amount: u64,
+}
+
+/// 荷兰拍卖参数
+///
+/// 想要的代币B数量从`token_b_wanted_amount`开始，在`start_time`到`end_time`之间
+/// 线性下降到`floor_token_b_amount`，之后保持底价不变
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct DutchAuction {
+    /// 开始降价的Unix时间戳
+    pub start_time: i64,
+    /// 降至底价的Unix时间戳
+    pub end_time: i64,
+    /// 底价，即拍卖结束后想要的代币B总数量
+    pub floor_token_b_amount: u64,
+}
+
+impl DutchAuction {
+    /// 校验拍卖参数，底价必须大于零且低于起始价
+    pub fn validate(&self, token_b_wanted_amount: u64) -> Result<()> {
+        require!(
+            self.start_time < self.end_time
+                && self.floor_token_b_amount > 0
+                && self.floor_token_b_amount < token_b_wanted_amount,
+            ErrorCode::InvalidDutchAuction
+        );
+        Ok(())
+    }
+
+    /// 计算给定时间点想要的代币B总数量
+    pub fn token_b_wanted_at(&self, token_b_wanted_amount: u64, now: i64) -> Result<u64> {
+        if now <= self.start_time {
+            return Ok(token_b_wanted_amount);
+        }
+        if now >= self.end_time {
+            return Ok(self.floor_token_b_amount);
+        }
+
+        // 向上取整，舍入误差由接受者承担
+        let decay_range = (token_b_wanted_amount - self.floor_token_b_amount) as u128;
+        let elapsed = (now - self.start_time) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        let decayed = decay_range
+            .checked_mul(elapsed)
+            .ok_or(ErrorCode::MathOverflow)?
+            / duration;
+
+        Ok(token_b_wanted_amount - decayed as u64)
+    }
+}
+
+/// 代币A的线性解锁计划
+///
+/// 接受者成交后代币A留在托管账户中，在`start_time`到`end_time`之间线性解锁，
+/// 接受者通过claim_vested提取已解锁部分
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct VestingSchedule {
+    /// 开始解锁的Unix时间戳
+    pub start_time: i64,
+    /// 全部解锁的Unix时间戳
+    pub end_time: i64,
+}
+
+impl VestingSchedule {
+    /// 校验解锁计划，结束时间必须晚于开始时间
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.start_time < self.end_time,
+            ErrorCode::InvalidVestingSchedule
+        );
+        Ok(())
+    }
+
+    /// 计算给定时间点已解锁的数量（向下取整）
+    pub fn unlocked_amount(&self, total_amount: u64, now: i64) -> Result<u64> {
+        if now <= self.start_time {
+            return Ok(0);
+        }
+        if now >= self.end_time {
+            return Ok(total_amount);
+        }
+
+        let elapsed = (now - self.start_time) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        let unlocked = (total_amount as u128)
+            .checked_mul(elapsed)
+            .ok_or(ErrorCode::MathOverflow)?
+            / duration;
+
+        Ok(unlocked as u64)
+    }
+}
 
 /// 报价信息结构体
-/// 
+///
 /// 存储链上报价的核心信息，包括交易对、数量、创建者等
 #[account]
 #[derive(InitSpace)]
@@ -16,8 +117,210 @@ pub struct Offer {
     pub token_mint_b: Pubkey,
     /// 创建者想要的代币B数量
     pub token_b_wanted_amount: u64,
+    /// 实际存入托管账户的代币A数量（已扣除转账手续费）
+    pub token_a_offered_amount: u64,
+    /// 尚未成交的代币A数量
+    pub token_a_remaining_amount: u64,
+    /// 尚未成交的代币B数量
+    pub token_b_remaining_amount: u64,
+    /// 报价过期的Unix时间戳，None表示永不过期
+    pub expires_at: Option<i64>,
+    /// 指定的唯一接受者，None表示不限制
+    pub allowed_taker: Option<Pubkey>,
+    /// 允许接受者名单的默克尔根，None表示不限制
+    pub taker_merkle_root: Option<[u8; 32]>,
+    /// 主交易对之外额外提供的代币A腿（一篮子报价）
+    #[max_len(MAX_BASKET_LEGS)]
+    pub extra_legs_a: Vec<OfferLeg>,
+    /// 主交易对之外额外想要的代币B腿（一篮子报价）
+    #[max_len(MAX_BASKET_LEGS)]
+    pub extra_legs_b: Vec<OfferLeg>,
+    /// 荷兰拍卖参数，None表示固定价格
+    pub dutch_auction: Option<DutchAuction>,
+    /// 报价修订号，创建者每次修改报价后加一
+    pub revision: u32,
+    /// 代币A的解锁计划，None表示成交时立即释放
+    pub vesting: Option<VestingSchedule>,
+    /// 成交后代币A的归属接受者，None表示尚未成交
+    pub vesting_beneficiary: Option<Pubkey>,
+    /// 成交时锁定给接受者的代币A总数量（已扣除手续费）
+    pub token_a_vesting_amount: u64,
+    /// 接受者已提取的代币A数量
+    pub token_a_claimed_amount: u64,
+    /// 仲裁人，设置后代币B在链下结算，托管的代币A只能由仲裁人或双方共同处置
+    pub arbiter: Option<Pubkey>,
+    /// 争议处理截止时间，之后创建者可单方面取回托管的代币A
+    pub dispute_deadline: Option<i64>,
     /// PDA账户的bump种子
     pub bump: u8,
     /// 报价是否已被取消的标志
     pub is_cancelled: bool,
-}
\ No newline at end of file
+}
+
+impl Offer {
+    /// 报价PDA作为托管账户权限签名时使用的种子
+    pub fn signer_seeds(&self) -> OfferSignerSeeds {
+        OfferSignerSeeds {
+            maker: self.maker,
+            offer_id: self.offer_id.to_le_bytes(),
+            bump: [self.bump],
+        }
+    }
+
+    /// 给定时间点尚未成交部分想要的代币B数量
+    ///
+    /// 固定价格报价即`token_b_remaining_amount`；荷兰拍卖按当前价格与起始价格之比
+    /// 折算剩余数量，向上取整
+    pub fn current_token_b_remaining_amount(&self, now: i64) -> Result<u64> {
+        let Some(dutch_auction) = self.dutch_auction else {
+            return Ok(self.token_b_remaining_amount);
+        };
+
+        let token_b_wanted_now =
+            dutch_auction.token_b_wanted_at(self.token_b_wanted_amount, now)?;
+        let numerator = (self.token_b_remaining_amount as u128)
+            .checked_mul(token_b_wanted_now as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let denominator = self.token_b_wanted_amount as u128;
+
+        Ok(numerator.div_ceil(denominator) as u64)
+    }
+
+    /// 根据本次支付的代币B数量，按剩余比例计算应释放的代币A数量
+    ///
+    /// 最后一笔成交直接释放全部剩余代币A，避免舍入残留；一篮子报价和带解锁计划的报价
+    /// 只能一次全部成交
+    pub fn token_a_for_fill(&self, token_b_fill_amount: u64, now: i64) -> Result<u64> {
+        let token_b_remaining_amount = self.current_token_b_remaining_amount(now)?;
+        require!(
+            token_b_fill_amount > 0 && token_b_fill_amount <= token_b_remaining_amount,
+            ErrorCode::InvalidFillAmount
+        );
+        require!(
+            !self.is_basket() || token_b_fill_amount == token_b_remaining_amount,
+            ErrorCode::BasketRequiresFullFill
+        );
+        require!(
+            self.vesting.is_none() || token_b_fill_amount == token_b_remaining_amount,
+            ErrorCode::VestingRequiresFullFill
+        );
+
+        if token_b_fill_amount == token_b_remaining_amount {
+            return Ok(self.token_a_remaining_amount);
+        }
+
+        // 向下取整，舍入误差由接受者承担
+        let token_a_amount = (self.token_a_remaining_amount as u128)
+            .checked_mul(token_b_fill_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / token_b_remaining_amount as u128;
+        require!(token_a_amount > 0, ErrorCode::FillAmountTooSmall);
+
+        Ok(token_a_amount as u64)
+    }
+
+    /// 扣减本次成交的剩余数量
+    ///
+    /// `token_b_amount`为按当前价格支付的数量，荷兰拍卖会折算回起始价格下的剩余数量
+    pub fn record_fill(
+        &mut self,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        let token_b_remaining_now = self.current_token_b_remaining_amount(now)?;
+        let token_b_filled_amount = if token_b_amount == token_b_remaining_now {
+            self.token_b_remaining_amount
+        } else {
+            ((self.token_b_remaining_amount as u128)
+                .checked_mul(token_b_amount as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                / token_b_remaining_now as u128) as u64
+        };
+
+        self.token_a_remaining_amount = self
+            .token_a_remaining_amount
+            .checked_sub(token_a_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.token_b_remaining_amount = self
+            .token_b_remaining_amount
+            .checked_sub(token_b_filled_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// 报价是否已全部成交
+    pub fn is_filled(&self) -> bool {
+        self.token_b_remaining_amount == 0
+    }
+
+    /// 是否为包含额外交易腿的一篮子报价
+    pub fn is_basket(&self) -> bool {
+        !self.extra_legs_a.is_empty() || !self.extra_legs_b.is_empty()
+    }
+
+    /// 是否已成交并正在向接受者解锁代币A（此时托管的代币A归属于接受者）
+    pub fn is_vesting(&self) -> bool {
+        self.vesting_beneficiary.is_some()
+    }
+
+    /// 给定时间点接受者可以提取的代币A数量
+    pub fn claimable_token_a_amount(&self, now: i64) -> Result<u64> {
+        let vesting = self.vesting.ok_or(ErrorCode::OfferNotVesting)?;
+        let unlocked = vesting.unlocked_amount(self.token_a_vesting_amount, now)?;
+        Ok(unlocked.saturating_sub(self.token_a_claimed_amount))
+    }
+
+    /// 是否为由仲裁人处置的链下结算报价
+    pub fn is_arbitrated(&self) -> bool {
+        self.arbiter.is_some()
+    }
+
+    /// 争议处理期限在给定时间点是否已过
+    pub fn is_dispute_expired(&self, now: i64) -> bool {
+        matches!(self.dispute_deadline, Some(dispute_deadline) if now >= dispute_deadline)
+    }
+
+    /// 报价在给定时间点是否已过期
+    pub fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at)
+    }
+
+    /// 检查接受者是否有权成交该报价
+    ///
+    /// 指定接受者时要求签名者与之相同；设置默克尔根时要求提供有效证明，
+    /// 叶子节点为 sha256(接受者公钥)，父节点为两个子节点按字节序排序后拼接的 sha256
+    pub fn check_taker(&self, taker: &Pubkey, taker_proof: &[[u8; 32]]) -> Result<()> {
+        if let Some(allowed_taker) = self.allowed_taker {
+            require_keys_eq!(*taker, allowed_taker, ErrorCode::TakerNotAllowed);
+        }
+
+        if let Some(root) = self.taker_merkle_root {
+            let mut node = hashv(&[taker.as_ref()]).to_bytes();
+            for sibling in taker_proof {
+                node = if node <= *sibling {
+                    hashv(&[&node, sibling]).to_bytes()
+                } else {
+                    hashv(&[sibling, &node]).to_bytes()
+                };
+            }
+            require!(node == root, ErrorCode::TakerNotAllowed);
+        }
+
+        Ok(())
+    }
+}
+
+/// 报价PDA的签名种子，seeds = [b"offer", maker, offer_id(小端序), bump]
+pub struct OfferSignerSeeds {
+    maker: Pubkey,
+    offer_id: [u8; 8],
+    bump: [u8; 1],
+}
+
+impl OfferSignerSeeds {
+    /// 按PDA推导顺序排列的种子
+    pub fn as_seeds(&self) -> [&[u8]; 4] {
+        [b"offer", self.maker.as_ref(), &self.offer_id, &self.bump]
+    }
+}
@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+
+use crate::{bps_of, error::ErrorCode, POOL_MINIMUM_LIQUIDITY};
+
+/// 恒定乘积流动性池
+///
+/// 以(代币A, 代币B)为种子的PDA，代币A地址小于代币B地址，每个交易对只有一个池子。
+/// 两种代币分别托管在池子的关联代币账户中，储备量即托管账户余额；
+/// LP代币的Mint同样由池子PDA管理
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    /// 代币A的Mint地址
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址
+    pub token_mint_b: Pubkey,
+    /// LP代币的Mint地址
+    pub lp_mint: Pubkey,
+    /// 兑换手续费，单位为基点，留在池中归LP所有
+    pub fee_bps: u16,
+    /// PDA账户的bump种子
+    pub bump: u8,
+}
+
+impl Pool {
+    /// 计算首次注入流动性铸造的LP代币数量
+    ///
+    /// 数量为两种代币数量的几何平均数，扣除永久锁定的最低流动性
+    pub fn initial_liquidity(token_a_amount: u64, token_b_amount: u64) -> Result<u64> {
+        let liquidity = isqrt(token_a_amount as u128 * token_b_amount as u128) as u64;
+        liquidity
+            .checked_sub(POOL_MINIMUM_LIQUIDITY)
+            .filter(|&liquidity| liquidity > 0)
+            .ok_or(ErrorCode::InsufficientLiquidity.into())
+    }
+
+    /// 按当前储备比例计算实际存入的代币数量，不超过调用者给出的上限
+    ///
+    /// # 返回值
+    /// * `Result<(u64, u64)>` - 存入的代币A和代币B数量
+    pub fn deposit_amounts(
+        reserve_a: u64,
+        reserve_b: u64,
+        max_token_a_amount: u64,
+        max_token_b_amount: u64,
+    ) -> Result<(u64, u64)> {
+        require!(
+            reserve_a > 0 && reserve_b > 0,
+            ErrorCode::InsufficientLiquidity
+        );
+        let token_b_amount = mul_div_ceil(max_token_a_amount, reserve_b, reserve_a)?;
+        if token_b_amount <= max_token_b_amount {
+            return Ok((max_token_a_amount, token_b_amount));
+        }
+        let token_a_amount = mul_div_ceil(max_token_b_amount, reserve_a, reserve_b)?;
+        Ok((token_a_amount.min(max_token_a_amount), max_token_b_amount))
+    }
+
+    /// 按实际到账的代币数量计算应铸造的LP代币数量，取两侧份额中较小者
+    pub fn deposit_liquidity(
+        token_a_received: u64,
+        token_b_received: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<u64> {
+        let total_liquidity = total_liquidity(lp_supply)?;
+        let liquidity = mul_div(token_a_received, total_liquidity, reserve_a)?.min(mul_div(
+            token_b_received,
+            total_liquidity,
+            reserve_b,
+        )?);
+        require!(liquidity > 0, ErrorCode::InsufficientLiquidity);
+        Ok(liquidity)
+    }
+
+    /// 计算赎回LP代币可取出的代币数量
+    ///
+    /// # 返回值
+    /// * `Result<(u64, u64)>` - 取出的代币A和代币B数量
+    pub fn withdraw_amounts(
+        lp_amount: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        lp_supply: u64,
+    ) -> Result<(u64, u64)> {
+        require!(
+            lp_amount > 0 && lp_amount <= lp_supply,
+            ErrorCode::InsufficientLiquidity
+        );
+        let total_liquidity = total_liquidity(lp_supply)?;
+        Ok((
+            mul_div(lp_amount, reserve_a, total_liquidity)?,
+            mul_div(lp_amount, reserve_b, total_liquidity)?,
+        ))
+    }
+
+    /// 按恒定乘积公式计算兑换输出数量，手续费从输入中扣除并留在池中
+    pub fn swap_amount_out(
+        &self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+    ) -> Result<u64> {
+        let amount_in_after_fee = amount_in
+            .checked_sub(bps_of(amount_in, self.fee_bps)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let amount_out = mul_div(
+            amount_in_after_fee,
+            reserve_out,
+            reserve_in
+                .checked_add(amount_in_after_fee)
+                .ok_or(ErrorCode::MathOverflow)?,
+        )?;
+        require!(amount_out > 0, ErrorCode::InsufficientLiquidity);
+        Ok(amount_out)
+    }
+}
+
+/// LP代币的流通量加上永久锁定的最低流动性
+fn total_liquidity(lp_supply: u64) -> Result<u64> {
+    lp_supply
+        .checked_add(POOL_MINIMUM_LIQUIDITY)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// 计算`a * b / c`，向下取整
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    require!(c > 0, ErrorCode::InsufficientLiquidity);
+    u64::try_from(a as u128 * b as u128 / c as u128).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// 计算`a * b / c`，向上取整
+fn mul_div_ceil(a: u64, b: u64, c: u64) -> Result<u64> {
+    require!(c > 0, ErrorCode::InsufficientLiquidity);
+    u64::try_from((a as u128 * b as u128).div_ceil(c as u128))
+        .map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// 整数平方根（牛顿迭代，向下取整）
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
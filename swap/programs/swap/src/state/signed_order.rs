@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::SIGNED_ORDER_DOMAIN;
+
+/// 报价创建者在链下签名的RFQ订单
+///
+/// 签名消息见`SignedOrder::message`，创建者无需预先托管代币，
+/// 成交时通过授权给程序PDA的代币额度直接从其代币A账户划转
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct SignedOrder {
+    /// 订单创建者公钥（签名者）
+    pub maker: Pubkey,
+    /// 代币A的Mint地址（创建者提供）
+    pub token_mint_a: Pubkey,
+    /// 代币B的Mint地址（创建者想要）
+    pub token_mint_b: Pubkey,
+    /// 创建者提供的代币A数量
+    pub token_a_amount: u64,
+    /// 创建者想要的代币B数量
+    pub token_b_amount: u64,
+    /// 订单随机数，每个创建者的随机数只能成交一次
+    pub nonce: u64,
+    /// 订单过期的Unix时间戳
+    pub expires_at: i64,
+}
+
+impl SignedOrder {
+    /// 创建者签名的消息：域标签 + 本程序ID + 订单的Borsh序列化结果
+    ///
+    /// 域标签和程序ID保证签名不能被其他程序或其他用途的签名消息复用
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut message = SIGNED_ORDER_DOMAIN.to_vec();
+        message.extend_from_slice(crate::ID.as_ref());
+        message.extend_from_slice(&self.try_to_vec()?);
+        Ok(message)
+    }
+}
+
+/// 已使用的订单随机数
+///
+/// 以(创建者, 随机数)为种子的PDA，账户存在即表示该订单已成交或已被创建者取消；
+/// 订单过期后不能再成交，账户可由支付租金者关闭
+#[account]
+#[derive(InitSpace)]
+pub struct OrderNonce {
+    /// 支付账户租金者（成交时为接受者，取消时为创建者），关闭时租金返还给该账户
+    pub payer: Pubkey,
+    /// 订单过期的Unix时间戳，之后账户可以关闭
+    pub expires_at: i64,
+    /// PDA账户的bump种子
+    pub bump: u8,
+}
@@ -0,0 +1,462 @@
+//! 在进程内的LiteSVM上运行swap程序的测试工具
+//!
+//! 程序需先用`anchor build`编译到`target/deploy/swap.so`，找不到编译产物时
+//! `Harness::new`直接panic，避免测试在没有程序的情况下悄悄通过
+
+use std::path::PathBuf;
+
+use anchor_lang::{
+    error::ERROR_CODE_OFFSET,
+    prelude::Pubkey,
+    solana_program::{
+        instruction::{Instruction, InstructionError},
+        program_option::COption,
+        program_pack::Pack,
+    },
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token_2022::spl_token_2022::state::{Account as TokenAccount, AccountState, Mint},
+};
+use litesvm::LiteSVM;
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_loader_v3_interface::{get_program_data_address, state::UpgradeableLoaderState};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+use swap::{error::ErrorCode, FeeSide};
+
+/// 测试账户初始持有的lamports
+const INITIAL_LAMPORTS: u64 = 10_000_000_000;
+
+/// 测试代币的精度
+pub const DECIMALS: u8 = 6;
+
+/// 加载了swap程序并初始化全局配置的LiteSVM环境
+pub struct Harness {
+    pub svm: LiteSVM,
+    /// 全局配置管理员，同时是测试代币的铸币权限
+    pub admin: Keypair,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// 创建测试环境，找不到编译好的程序时panic
+    pub fn new() -> Self {
+        let program_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/deploy/swap.so");
+        assert!(
+            program_path.exists(),
+            "{} not found, run `anchor build` first",
+            program_path.display()
+        );
+
+        let mut harness = Self {
+            svm: LiteSVM::new(),
+            admin: Keypair::new(),
+        };
+        harness.fund(&harness.admin.pubkey());
+        let program = std::fs::read(&program_path).expect("failed to read the swap program");
+        harness.deploy_upgradeable_program(&program);
+
+        let admin = harness.admin.pubkey();
+        let initialize_config = Instruction {
+            program_id: swap::ID,
+            accounts: swap::accounts::InitializeConfig {
+                admin,
+                program_data: program_data_address(),
+                config: config_address(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: swap::instruction::InitializeConfig {
+                fee_bps: 0,
+                fee_recipient: admin,
+                fee_side: FeeSide::TokenB,
+                max_referral_bps: 0,
+            }
+            .data(),
+        };
+        let admin_keypair = harness.admin.insecure_clone();
+        harness
+            .send(initialize_config, &[&admin_keypair])
+            .expect("failed to initialize the config");
+
+        harness
+    }
+
+    /// 以可升级程序的形式部署swap程序，升级权限为管理员
+    ///
+    /// 初始化全局配置要求签名者是程序的升级权限，因此不能使用不可升级的加载方式
+    fn deploy_upgradeable_program(&mut self, program: &[u8]) {
+        let program_data = program_data_address();
+        let mut data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(self.admin.pubkey()),
+        })
+        .unwrap();
+        data.extend_from_slice(program);
+        self.set_loader_account(program_data, data, false);
+
+        let data = bincode::serialize(&UpgradeableLoaderState::Program {
+            programdata_address: program_data,
+        })
+        .unwrap();
+        self.set_loader_account(swap::ID, data, true);
+    }
+
+    fn set_loader_account(&mut self, address: Pubkey, data: Vec<u8>, executable: bool) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len());
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports,
+                    data,
+                    owner: solana_sdk_ids::bpf_loader_upgradeable::ID,
+                    executable,
+                    rent_epoch: 0,
+                },
+            )
+            .expect("failed to deploy the swap program");
+    }
+
+    /// 为账户充值lamports
+    pub fn fund(&mut self, address: &Pubkey) {
+        self.svm
+            .airdrop(address, INITIAL_LAMPORTS)
+            .expect("airdrop failed");
+    }
+
+    /// 创建一个有lamports的新用户
+    pub fn create_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.fund(&user.pubkey());
+        user
+    }
+
+    /// 直接写入一个没有冻结权限的代币Mint账户
+    pub fn create_mint(&mut self, token_program: &Pubkey) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                mint_authority: COption::Some(self.admin.pubkey()),
+                supply: u64::MAX / 2,
+                decimals: DECIMALS,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.set_token_program_account(mint, data, token_program);
+        mint
+    }
+
+    /// 直接写入用户的关联代币账户并设置余额
+    pub fn create_token_account(
+        &mut self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let address = get_associated_token_address_with_program_id(owner, mint, token_program);
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                delegate: COption::None,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                delegated_amount: 0,
+                close_authority: COption::None,
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.set_token_program_account(address, data, token_program);
+        address
+    }
+
+    fn set_token_program_account(&mut self, address: Pubkey, data: Vec<u8>, owner: &Pubkey) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len());
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports,
+                    data,
+                    owner: *owner,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    /// 读取代币账户余额，账户不存在时返回None
+    pub fn token_balance(&self, address: &Pubkey) -> Option<u64> {
+        let account = self.svm.get_account(address)?;
+        if account.data.is_empty() {
+            return None;
+        }
+        Some(
+            TokenAccount::unpack_from_slice(&account.data[..TokenAccount::LEN])
+                .ok()?
+                .amount,
+        )
+    }
+
+    /// 账户是否存在
+    pub fn account_exists(&self, address: &Pubkey) -> bool {
+        self.svm
+            .get_account(address)
+            .is_some_and(|account| account.lamports > 0)
+    }
+
+    /// 以第一个签名者为手续费支付者发送单条指令的交易
+    ///
+    /// 每笔交易后更换最新区块哈希，使内容相同的交易也能再次发送
+    pub fn send(
+        &mut self,
+        instruction: Instruction,
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self
+            .svm
+            .send_transaction(transaction)
+            .map(|_| ())
+            .map_err(|failed| failed.err);
+        self.svm.expire_blockhash();
+        result
+    }
+}
+
+/// 全局配置账户地址
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &swap::ID).0
+}
+
+/// swap程序的程序数据账户地址
+pub fn program_data_address() -> Pubkey {
+    get_program_data_address(&swap::ID)
+}
+
+/// 创建者档案账户地址
+pub fn maker_profile_address(maker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"maker_profile", maker.as_ref()], &swap::ID).0
+}
+
+/// 报价账户地址
+pub fn offer_address(maker: &Pubkey, offer_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"offer", maker.as_ref(), &offer_id.to_le_bytes()],
+        &swap::ID,
+    )
+    .0
+}
+
+/// 记录事件的事件权限账户地址
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &swap::ID).0
+}
+
+/// 报价托管账户地址
+pub fn vault_address(offer: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(offer, mint, token_program)
+}
+
+/// 一个报价涉及的交易对与代币程序
+#[derive(Clone, Copy)]
+pub struct Pair {
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// 构造以固定价格出售全部代币A的make_offer指令
+pub fn make_offer_instruction(
+    maker: &Pubkey,
+    offer_id: u64,
+    pair: &Pair,
+    token_a_offered_amount: u64,
+    token_b_wanted_amount: u64,
+) -> Instruction {
+    let offer = offer_address(maker, offer_id);
+    Instruction {
+        program_id: swap::ID,
+        accounts: swap::accounts::MakeOffer {
+            maker: *maker,
+            token_mint_a: pair.token_mint_a,
+            token_mint_b: pair.token_mint_b,
+            maker_token_account_a: Some(get_associated_token_address_with_program_id(
+                maker,
+                &pair.token_mint_a,
+                &pair.token_program,
+            )),
+            config: config_address(),
+            maker_profile: maker_profile_address(maker),
+            offer,
+            vault: Some(vault_address(
+                &offer,
+                &pair.token_mint_a,
+                &pair.token_program,
+            )),
+            system_program: system_program::ID,
+            token_program_a: pair.token_program,
+            token_program_b: pair.token_program,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: swap::ID,
+        }
+        .to_account_metas(None),
+        data: swap::instruction::MakeOffer {
+            token_a_offered_amount,
+            token_b_wanted_amount,
+            expires_at: None,
+            allowed_taker: None,
+            taker_merkle_root: None,
+            extra_legs_a: Vec::new(),
+            extra_legs_b: Vec::new(),
+            dutch_auction: None,
+            vesting: None,
+            arbiter: None,
+            dispute_deadline: None,
+        }
+        .data(),
+    }
+}
+
+/// 构造全部成交报价的take_offer指令，不限制滑点
+pub fn take_offer_instruction(
+    taker: &Pubkey,
+    maker: &Pubkey,
+    offer_id: u64,
+    pair: &Pair,
+    token_b_fill_amount: u64,
+) -> Instruction {
+    let offer = offer_address(maker, offer_id);
+    let token_account = |owner: &Pubkey, mint: &Pubkey| {
+        Some(get_associated_token_address_with_program_id(
+            owner,
+            mint,
+            &pair.token_program,
+        ))
+    };
+    Instruction {
+        program_id: swap::ID,
+        accounts: swap::accounts::TakeOffer {
+            taker: *taker,
+            maker: *maker,
+            token_mint_a: pair.token_mint_a,
+            token_mint_b: pair.token_mint_b,
+            taker_token_account_a: token_account(taker, &pair.token_mint_a),
+            taker_token_account_b: token_account(taker, &pair.token_mint_b),
+            maker_token_account_b: token_account(maker, &pair.token_mint_b),
+            offer,
+            maker_profile: maker_profile_address(maker),
+            vault: Some(vault_address(
+                &offer,
+                &pair.token_mint_a,
+                &pair.token_program,
+            )),
+            config: config_address(),
+            fee_token_account: None,
+            fee_recipient: None,
+            referrer_token_account: None,
+            nft_metadata: None,
+            system_program: system_program::ID,
+            token_program_a: pair.token_program,
+            token_program_b: pair.token_program,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: swap::ID,
+        }
+        .to_account_metas(None),
+        data: swap::instruction::TakeOffer {
+            token_b_fill_amount,
+            taker_proof: Vec::new(),
+            expected_revision: 0,
+            expected_token_a_amount: 0,
+            max_token_b_amount: u64::MAX,
+            referral_bps: 0,
+        }
+        .data(),
+    }
+}
+
+/// 构造cancel_offer指令，`signer`为发起撤销的账户
+///
+/// `with_vault`为false时不传托管账户和创建者代币账户（托管账户已关闭时使用）
+pub fn cancel_offer_instruction(
+    signer: &Pubkey,
+    maker: &Pubkey,
+    offer_id: u64,
+    pair: &Pair,
+    with_vault: bool,
+) -> Instruction {
+    let offer = offer_address(maker, offer_id);
+    Instruction {
+        program_id: swap::ID,
+        accounts: swap::accounts::CancelOffer {
+            maker: *signer,
+            token_mint_a: pair.token_mint_a,
+            maker_token_account_a: with_vault.then(|| {
+                get_associated_token_address_with_program_id(
+                    signer,
+                    &pair.token_mint_a,
+                    &pair.token_program,
+                )
+            }),
+            offer,
+            maker_profile: maker_profile_address(maker),
+            vault: with_vault
+                .then(|| vault_address(&offer, &pair.token_mint_a, &pair.token_program)),
+            system_program: system_program::ID,
+            token_program_a: pair.token_program,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: swap::ID,
+        }
+        .to_account_metas(None),
+        data: swap::instruction::CancelOffer {
+            _offer_id: offer_id,
+        }
+        .data(),
+    }
+}
+
+/// 交易失败的原因是否为swap程序返回的指定错误
+pub fn is_swap_error(error: &TransactionError, expected: ErrorCode) -> bool {
+    matches!(
+        error,
+        TransactionError::InstructionError(_, InstructionError::Custom(code))
+            if *code == ERROR_CODE_OFFSET + expected as u32
+    )
+}
+
+/// 交易失败的原因是否为Anchor框架返回的指定错误（如账户约束检查失败）
+pub fn is_anchor_error(error: &TransactionError, expected: anchor_lang::error::ErrorCode) -> bool {
+    matches!(
+        error,
+        TransactionError::InstructionError(_, InstructionError::Custom(code))
+            if *code == expected as u32
+    )
+}
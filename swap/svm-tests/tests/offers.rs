@@ -0,0 +1,299 @@
+//! 报价创建、成交与撤销的集成测试，分别在SPL Token和Token-2022代币上运行
+
+use anchor_lang::{error::ErrorCode as AnchorErrorCode, prelude::Pubkey};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use swap::error::ErrorCode;
+use swap_svm_tests::{
+    cancel_offer_instruction, is_anchor_error, is_swap_error, make_offer_instruction,
+    offer_address, take_offer_instruction, vault_address, Harness, Pair,
+};
+
+/// 创建者初始持有的代币A数量
+const MAKER_TOKEN_A_AMOUNT: u64 = 1_000_000_000;
+/// 接受者初始持有的代币B数量
+const TAKER_TOKEN_B_AMOUNT: u64 = 1_000_000_000;
+/// 报价出售的代币A数量
+const TOKEN_A_OFFERED_AMOUNT: u64 = 1_000_000;
+/// 报价想要的代币B数量
+const TOKEN_B_WANTED_AMOUNT: u64 = 2_000_000;
+/// 创建者档案为第一个报价分配的ID
+const FIRST_OFFER_ID: u64 = 0;
+
+struct Fixture {
+    harness: Harness,
+    maker: Keypair,
+    taker: Keypair,
+    pair: Pair,
+}
+
+impl Fixture {
+    /// 创建交易对并为双方准备代币
+    fn new(token_program: Pubkey) -> Self {
+        let mut harness = Harness::new();
+        let maker = harness.create_user();
+        let taker = harness.create_user();
+        let pair = Pair {
+            token_mint_a: harness.create_mint(&token_program),
+            token_mint_b: harness.create_mint(&token_program),
+            token_program,
+        };
+        harness.create_token_account(
+            &maker.pubkey(),
+            &pair.token_mint_a,
+            &token_program,
+            MAKER_TOKEN_A_AMOUNT,
+        );
+        harness.create_token_account(
+            &taker.pubkey(),
+            &pair.token_mint_b,
+            &token_program,
+            TAKER_TOKEN_B_AMOUNT,
+        );
+        Self {
+            harness,
+            maker,
+            taker,
+            pair,
+        }
+    }
+
+    fn token_account(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        anchor_spl::associated_token::get_associated_token_address_with_program_id(
+            owner,
+            mint,
+            &self.pair.token_program,
+        )
+    }
+
+    fn vault(&self) -> Pubkey {
+        vault_address(
+            &offer_address(&self.maker.pubkey(), FIRST_OFFER_ID),
+            &self.pair.token_mint_a,
+            &self.pair.token_program,
+        )
+    }
+
+    fn make_offer(&mut self) {
+        let instruction = make_offer_instruction(
+            &self.maker.pubkey(),
+            FIRST_OFFER_ID,
+            &self.pair,
+            TOKEN_A_OFFERED_AMOUNT,
+            TOKEN_B_WANTED_AMOUNT,
+        );
+        let maker = self.maker.insecure_clone();
+        self.harness
+            .send(instruction, &[&maker])
+            .expect("make_offer failed");
+    }
+}
+
+fn make_offer_escrows_token_a(token_program: Pubkey) {
+    let mut fixture = Fixture::new(token_program);
+    fixture.make_offer();
+
+    let maker_token_account_a =
+        fixture.token_account(&fixture.maker.pubkey(), &fixture.pair.token_mint_a);
+    assert_eq!(
+        fixture.harness.token_balance(&fixture.vault()),
+        Some(TOKEN_A_OFFERED_AMOUNT)
+    );
+    assert_eq!(
+        fixture.harness.token_balance(&maker_token_account_a),
+        Some(MAKER_TOKEN_A_AMOUNT - TOKEN_A_OFFERED_AMOUNT)
+    );
+}
+
+fn take_offer_swaps_both_legs(token_program: Pubkey) {
+    let mut fixture = Fixture::new(token_program);
+    fixture.make_offer();
+
+    let taker = fixture.taker.insecure_clone();
+    let instruction = take_offer_instruction(
+        &taker.pubkey(),
+        &fixture.maker.pubkey(),
+        FIRST_OFFER_ID,
+        &fixture.pair,
+        TOKEN_B_WANTED_AMOUNT,
+    );
+    fixture
+        .harness
+        .send(instruction, &[&taker])
+        .expect("take_offer failed");
+
+    let taker_token_account_a = fixture.token_account(&taker.pubkey(), &fixture.pair.token_mint_a);
+    let maker_token_account_b =
+        fixture.token_account(&fixture.maker.pubkey(), &fixture.pair.token_mint_b);
+    assert_eq!(
+        fixture.harness.token_balance(&taker_token_account_a),
+        Some(TOKEN_A_OFFERED_AMOUNT)
+    );
+    assert_eq!(
+        fixture.harness.token_balance(&maker_token_account_b),
+        Some(TOKEN_B_WANTED_AMOUNT)
+    );
+    // 全部成交后报价账户和托管账户都被关闭
+    assert!(!fixture
+        .harness
+        .account_exists(&offer_address(&fixture.maker.pubkey(), FIRST_OFFER_ID)));
+    assert!(!fixture.harness.account_exists(&fixture.vault()));
+}
+
+fn take_offer_rejects_wrong_mint(token_program: Pubkey) {
+    let mut fixture = Fixture::new(token_program);
+    fixture.make_offer();
+
+    // 接受者用另一种代币冒充报价想要的代币B
+    let wrong_mint = fixture.harness.create_mint(&token_program);
+    let taker = fixture.taker.insecure_clone();
+    fixture.harness.create_token_account(
+        &taker.pubkey(),
+        &wrong_mint,
+        &token_program,
+        TAKER_TOKEN_B_AMOUNT,
+    );
+    let wrong_pair = Pair {
+        token_mint_b: wrong_mint,
+        ..fixture.pair
+    };
+    let instruction = take_offer_instruction(
+        &taker.pubkey(),
+        &fixture.maker.pubkey(),
+        FIRST_OFFER_ID,
+        &wrong_pair,
+        TOKEN_B_WANTED_AMOUNT,
+    );
+    let error = fixture
+        .harness
+        .send(instruction, &[&taker])
+        .expect_err("take_offer with the wrong token B should fail");
+    assert!(is_anchor_error(&error, AnchorErrorCode::ConstraintHasOne));
+
+    assert_eq!(
+        fixture.harness.token_balance(&fixture.vault()),
+        Some(TOKEN_A_OFFERED_AMOUNT)
+    );
+}
+
+fn cancel_offer_refunds_maker(token_program: Pubkey) {
+    let mut fixture = Fixture::new(token_program);
+    fixture.make_offer();
+
+    let maker = fixture.maker.insecure_clone();
+    let instruction = cancel_offer_instruction(
+        &maker.pubkey(),
+        &maker.pubkey(),
+        FIRST_OFFER_ID,
+        &fixture.pair,
+        true,
+    );
+    fixture
+        .harness
+        .send(instruction, &[&maker])
+        .expect("cancel_offer failed");
+
+    let maker_token_account_a = fixture.token_account(&maker.pubkey(), &fixture.pair.token_mint_a);
+    assert_eq!(
+        fixture.harness.token_balance(&maker_token_account_a),
+        Some(MAKER_TOKEN_A_AMOUNT)
+    );
+    assert!(!fixture.harness.account_exists(&fixture.vault()));
+}
+
+fn cancel_offer_rejects_wrong_maker(token_program: Pubkey) {
+    let mut fixture = Fixture::new(token_program);
+    fixture.make_offer();
+
+    // 接受者试图以自己的签名撤销创建者的报价；先为其创建代币A账户，
+    // 使失败来自报价账户的校验而不是账户反序列化
+    let taker = fixture.taker.insecure_clone();
+    fixture.harness.create_token_account(
+        &taker.pubkey(),
+        &fixture.pair.token_mint_a,
+        &token_program,
+        0,
+    );
+    let instruction = cancel_offer_instruction(
+        &taker.pubkey(),
+        &fixture.maker.pubkey(),
+        FIRST_OFFER_ID,
+        &fixture.pair,
+        true,
+    );
+    // 报价地址按签名者重新推导，与创建者的报价不一致
+    let error = fixture
+        .harness
+        .send(instruction, &[&taker])
+        .expect_err("cancel_offer by the taker should fail");
+    assert!(is_anchor_error(&error, AnchorErrorCode::ConstraintSeeds));
+
+    assert_eq!(
+        fixture.harness.token_balance(&fixture.vault()),
+        Some(TOKEN_A_OFFERED_AMOUNT)
+    );
+}
+
+fn cancel_offer_rejects_double_cancel(token_program: Pubkey) {
+    let mut fixture = Fixture::new(token_program);
+    fixture.make_offer();
+
+    let maker = fixture.maker.insecure_clone();
+    let instruction = cancel_offer_instruction(
+        &maker.pubkey(),
+        &maker.pubkey(),
+        FIRST_OFFER_ID,
+        &fixture.pair,
+        true,
+    );
+    fixture
+        .harness
+        .send(instruction, &[&maker])
+        .expect("cancel_offer failed");
+
+    // 托管账户已在第一次撤销时关闭，第二次撤销不传托管账户
+    let instruction = cancel_offer_instruction(
+        &maker.pubkey(),
+        &maker.pubkey(),
+        FIRST_OFFER_ID,
+        &fixture.pair,
+        false,
+    );
+    let error = fixture
+        .harness
+        .send(instruction, &[&maker])
+        .expect_err("second cancel_offer should fail");
+    assert!(is_swap_error(&error, ErrorCode::OfferAlreadyCancelled));
+}
+
+/// 为每个测试场景分别生成SPL Token和Token-2022两个测试
+macro_rules! for_both_token_programs {
+    ($($scenario:ident),* $(,)?) => {
+        mod spl_token {
+            $(
+                #[test]
+                fn $scenario() {
+                    super::$scenario(anchor_spl::token::ID);
+                }
+            )*
+        }
+
+        mod token_2022 {
+            $(
+                #[test]
+                fn $scenario() {
+                    super::$scenario(anchor_spl::token_2022::ID);
+                }
+            )*
+        }
+    };
+}
+
+for_both_token_programs!(
+    make_offer_escrows_token_a,
+    take_offer_swaps_both_legs,
+    take_offer_rejects_wrong_mint,
+    cancel_offer_refunds_maker,
+    cancel_offer_rejects_wrong_maker,
+    cancel_offer_rejects_double_cancel,
+);
@@ -4,13 +4,32 @@ import { type Program } from "@coral-xyz/anchor";
 import pkg from "@coral-xyz/anchor";
 const { BN } = pkg;
 import {
+  ExtensionType,
+  NATIVE_MINT,
   TOKEN_2022_PROGRAM_ID,
-  type TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  approve,
+  createAssociatedTokenAccountIdempotent,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeTransferHookInstruction,
+  createMint,
   getAssociatedTokenAddressSync,
+  getMintLen,
+  mintTo,
 } from "@solana/spl-token";
-import { LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import {
+  Ed25519Program,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
 import { assert } from "chai";
 import type { Swap } from "../target/types/swap";
+import type { TransferHook } from "../target/types/transfer_hook";
 
 import {
   confirmTransaction,
@@ -29,6 +48,9 @@ const SECONDS = 1000;
 // network IO, these tests usually take about 15 seconds.
 const ANCHOR_SLOW_TEST_THRESHOLD = 40 * SECONDS;
 
+// 与程序中的MAX_MARKET_OFFERS保持一致
+const MAX_MARKET_OFFERS = 32;
+
 const getRandomBigNumber = (size = 8) => {
   return new BN(randomBytes(size));
 };
@@ -48,7 +70,8 @@ describe("swap", async () => {
 
   // We're going to reuse these accounts across multiple tests
   const accounts: Record<string, PublicKey> = {
-    tokenProgram: TOKEN_PROGRAM,
+    tokenProgramA: TOKEN_PROGRAM,
+    tokenProgramB: TOKEN_PROGRAM,
   };
 
   let alice: anchor.web3.Keypair;
@@ -60,6 +83,48 @@ describe("swap", async () => {
 
   const tokenAOfferedAmount = new BN(1_000_000);
   const tokenBWantedAmount = new BN(1_000_000);
+  const U64_MAX = new BN("18446744073709551615");
+
+  // 报价ID由创建者档案按顺序分配，档案不存在时从0开始
+  const makerProfileAddress = (maker: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("maker_profile"), maker.toBuffer()],
+      program.programId
+    )[0];
+
+  // 本程序的程序数据账户，记录程序的升级权限
+  const programDataAddress = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  )[0];
+
+  const nextOfferId = async (maker: PublicKey) => {
+    const makerProfile = await program.account.makerProfile.fetchNullable(
+      makerProfileAddress(maker)
+    );
+    return makerProfile ? makerProfile.nextOfferId : new BN(0);
+  };
+
+  // 事件通过程序自调用的内部指令记录，指令数据为8字节标记加事件数据
+  const emittedEvents = async (signature: string) => {
+    const transaction = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const accountKeys = transaction.transaction.message.getAccountKeys();
+    return transaction.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .filter((instruction) =>
+        accountKeys.get(instruction.programIdIndex).equals(program.programId)
+      )
+      .map((instruction) =>
+        program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(
+            anchor.utils.bytes.bs58.decode(instruction.data).subarray(8)
+          )
+        )
+      );
+  };
 
   before(
     "Creates Alice and Bob accounts, 2 token mints, and associated token accounts for both tokens for both users",
@@ -112,12 +177,33 @@ describe("swap", async () => {
       accounts.tokenMintB = tokenMintB.publicKey;
       accounts.makerTokenAccountB = aliceTokenAccountB;
       accounts.takerTokenAccountB = bobTokenAccountB;
+
+      // 初始化全局配置，手续费接收者为payer，初始手续费为0
+      accounts.feeTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        accounts.tokenMintB,
+        payer.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .initializeConfig(0, payer.publicKey, { tokenB: {} }, 0)
+          .accountsPartial({
+            admin: payer.publicKey,
+            programData: programDataAddress,
+          })
+          .rpc()
+      );
     }
   );
 
   it("Puts the tokens Alice offers into the vault when Alice makes an offer", async () => {
-    // Pick a random ID for the offer we'll make
-    const offerId = getRandomBigNumber();
+    // Alice's maker profile assigns the ID of the offer we'll make
+    const offerId = await nextOfferId(accounts.maker);
 
     // Then determine the account addresses we'll use for the offer and the vault
     const offer = PublicKey.findProgramAddressSync(
@@ -140,7 +226,19 @@ describe("swap", async () => {
     accounts.vault = vault;
 
     const transactionSignature = await program.methods
-      .makeOffer(offerId, tokenAOfferedAmount, tokenBWantedAmount)
+      .makeOffer(
+        tokenAOfferedAmount,
+        tokenBWantedAmount,
+        null,
+        null,
+        null,
+        [],
+        [],
+        null,
+        null,
+        null,
+        null
+      )
       .accounts({ ...accounts })
       .signers([alice])
       .rpc();
@@ -163,7 +261,7 @@ describe("swap", async () => {
 
   it("Puts the tokens from the vault into Bob's account, and gives Alice Bob's tokens, when Bob takes an offer", async () => {
     const transactionSignature = await program.methods
-      .takeOffer()
+      .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
       .accounts({ ...accounts })
       .signers([bob])
       .rpc();
@@ -189,12 +287,4239 @@ describe("swap", async () => {
     assert(aliceTokenAccountBalanceAfter.eq(tokenBWantedAmount));
   }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
 
+  // 测试协议手续费功能
+  describe("protocol fee", async () => {
+    let offerId: BN;
+    const feeBps = 100;
+
+    it("Routes the protocol fee from the token B leg to the fee account", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, feeBps, null, null, null, null)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const feeAccounts = { ...accounts, offer, vault };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...feeAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const feeBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.feeTokenAccount)
+        ).value.amount
+      );
+      const aliceTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...feeAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const expectedFee = tokenBWantedAmount.muln(feeBps).divn(10_000);
+
+      const feeBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.feeTokenAccount)
+        ).value.amount
+      );
+      const aliceTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+
+      assert(feeBalanceAfter.sub(feeBalanceBefore).eq(expectedFee));
+      assert(
+        aliceTokenAccountBBalanceAfter
+          .sub(aliceTokenAccountBBalanceBefore)
+          .eq(tokenBWantedAmount.sub(expectedFee))
+      );
+
+      // 恢复为零手续费，避免影响其他测试
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, 0, null, null, null, null)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试代币A与代币B使用不同代币程序的报价
+  describe("mixed token programs", async () => {
+    let offerId: BN;
+
+    it("Swaps a Token Extensions mint against a classic SPL Token mint", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      // 使用经典代币程序创建代币C，并铸造给Bob
+      const classicMint = await createMint(
+        connection,
+        payer,
+        payer.publicKey,
+        null,
+        6,
+        undefined,
+        {},
+        TOKEN_PROGRAM_ID
+      );
+      const bobClassicTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        classicMint,
+        bob.publicKey,
+        {},
+        TOKEN_PROGRAM_ID
+      );
+      await mintTo(
+        connection,
+        payer,
+        classicMint,
+        bobClassicTokenAccount,
+        payer,
+        1_000_000_000,
+        [],
+        {},
+        TOKEN_PROGRAM_ID
+      );
+      const feeClassicTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        classicMint,
+        payer.publicKey,
+        {},
+        TOKEN_PROGRAM_ID
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const aliceClassicTokenAccount = getAssociatedTokenAddressSync(
+        classicMint,
+        alice.publicKey,
+        false,
+        TOKEN_PROGRAM_ID
+      );
+
+      const mixedAccounts = {
+        ...accounts,
+        tokenMintB: classicMint,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+        makerTokenAccountB: aliceClassicTokenAccount,
+        takerTokenAccountB: bobClassicTokenAccount,
+        feeTokenAccount: feeClassicTokenAccount,
+        offer,
+        vault,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...mixedAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...mixedAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const aliceClassicBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(aliceClassicTokenAccount)
+        ).value.amount
+      );
+      assert(aliceClassicBalance.eq(tokenBWantedAmount));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试带转账手续费的Token-2022代币
+  describe("transfer fee mints", async () => {
+    let offerId: BN;
+
+    it("Grosses up Bob's payment so Alice receives exactly what she wants", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      // 创建带1%转账手续费的代币B
+      const [feeMint] = makeKeypairs(1);
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: payer.publicKey,
+            newAccountPubkey: feeMint.publicKey,
+            space: mintLen,
+            lamports: await connection.getMinimumBalanceForRentExemption(
+              mintLen
+            ),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            feeMint.publicKey,
+            payer.publicKey,
+            payer.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(
+            feeMint.publicKey,
+            6,
+            payer.publicKey,
+            null,
+            TOKEN_2022_PROGRAM_ID
+          )
+        ),
+        [payer, feeMint]
+      );
+
+      const bobFeeMintTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        feeMint.publicKey,
+        bob.publicKey,
+        {},
+        TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(
+        connection,
+        payer,
+        feeMint.publicKey,
+        bobFeeMintTokenAccount,
+        payer,
+        1_000_000_000,
+        [],
+        {},
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const aliceFeeMintTokenAccount = getAssociatedTokenAddressSync(
+        feeMint.publicKey,
+        alice.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const feeMintAccounts = {
+        ...accounts,
+        tokenMintB: feeMint.publicKey,
+        tokenProgramB: TOKEN_2022_PROGRAM_ID,
+        makerTokenAccountB: aliceFeeMintTokenAccount,
+        takerTokenAccountB: bobFeeMintTokenAccount,
+        feeTokenAccount: await createAssociatedTokenAccountIdempotent(
+          connection,
+          payer,
+          feeMint.publicKey,
+          payer.publicKey,
+          {},
+          TOKEN_2022_PROGRAM_ID
+        ),
+        offer,
+        vault,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...feeMintAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...feeMintAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const aliceFeeMintBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(aliceFeeMintTokenAccount)
+        ).value.amount
+      );
+      assert(aliceFeeMintBalance.eq(tokenBWantedAmount));
+
+      // Bob额外承担了转账手续费
+      const bobFeeMintBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(bobFeeMintTokenAccount)
+        ).value.amount
+      );
+      assert(bobFeeMintBalance.lt(new BN(1_000_000_000).sub(tokenBWantedAmount)));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试一篮子报价功能
+  describe("basket offers", async () => {
+    let offerId: BN;
+    const extraAmount = new BN(500_000);
+
+    it("Settles every leg of a basket offer atomically", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      // 为Alice创建额外的代币C，作为代币A侧的第二条腿
+      const extraMint = await createMint(
+        connection,
+        payer,
+        payer.publicKey,
+        null,
+        6,
+        undefined,
+        {},
+        TOKEN_PROGRAM
+      );
+      const aliceExtraTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        extraMint,
+        alice.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+      await mintTo(
+        connection,
+        payer,
+        extraMint,
+        aliceExtraTokenAccount,
+        payer,
+        1_000_000_000,
+        [],
+        {},
+        TOKEN_PROGRAM
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const extraVault = getAssociatedTokenAddressSync(
+        extraMint,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const bobExtraTokenAccount = getAssociatedTokenAddressSync(
+        extraMint,
+        bob.publicKey,
+        false,
+        TOKEN_PROGRAM
+      );
+
+      const basketAccounts = { ...accounts, offer, vault };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [{ mint: extraMint, amount: extraAmount }],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...basketAccounts })
+          .remainingAccounts([
+            { pubkey: extraMint, isSigner: false, isWritable: false },
+            { pubkey: aliceExtraTokenAccount, isSigner: false, isWritable: true },
+            { pubkey: extraVault, isSigner: false, isWritable: true },
+          ])
+          .signers([alice])
+          .rpc()
+      );
+
+      const extraVaultBalance = new BN(
+        (await connection.getTokenAccountBalance(extraVault)).value.amount
+      );
+      assert(extraVaultBalance.eq(extraAmount));
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...basketAccounts })
+          .remainingAccounts([
+            { pubkey: extraMint, isSigner: false, isWritable: false },
+            { pubkey: extraVault, isSigner: false, isWritable: true },
+            { pubkey: bobExtraTokenAccount, isSigner: false, isWritable: true },
+          ])
+          .signers([bob])
+          .rpc()
+      );
+
+      const bobExtraBalance = new BN(
+        (await connection.getTokenAccountBalance(bobExtraTokenAccount)).value
+          .amount
+      );
+      assert(bobExtraBalance.eq(extraAmount));
+      assert.isNull(await connection.getAccountInfo(extraVault));
+      assert.isNull(await connection.getAccountInfo(offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试部分成交功能
+  describe("partial fills", async () => {
+    let offerId: BN;
+    const halfOfTokenBWanted = tokenBWantedAmount.divn(2);
+
+    it("Lets Bob fill half of an offer and keeps the offer open", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      accounts.offer = offer;
+      accounts.vault = vault;
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(halfOfTokenBWanted, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      // 资金库中应剩余一半的代币A
+      const vaultBalance = new BN(
+        (await connection.getTokenAccountBalance(vault)).value.amount
+      );
+      assert(vaultBalance.eq(tokenAOfferedAmount.divn(2)));
+
+      // 报价账户仍然存在，并记录剩余数量
+      const offerAccount = await program.account.offer.fetch(offer);
+      assert(offerAccount.tokenARemainingAmount.eq(tokenAOfferedAmount.divn(2)));
+      assert(
+        offerAccount.tokenBRemainingAmount.eq(
+          tokenBWantedAmount.sub(halfOfTokenBWanted)
+        )
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Closes the offer and the vault once the remainder is filled", async () => {
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount.sub(halfOfTokenBWanted),
+            [],
+            0,
+            new BN(0),
+            U64_MAX,
+            0
+          )
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      assert.isNull(await connection.getAccountInfo(accounts.offer));
+      assert.isNull(await connection.getAccountInfo(accounts.vault));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试指定接受者的私有报价
+  describe("private offers", async () => {
+    let offerId: BN;
+
+    it("Prevents Bob from taking an offer pinned to another taker", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const privateAccounts = { ...accounts, offer, vault };
+      const [counterparty] = makeKeypairs(1);
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            counterparty.publicKey,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...privateAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      try {
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...privateAccounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(
+          error.toString(),
+          "Taker is not allowed to take this offer"
+        );
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试修改报价功能
+  describe("update offer", async () => {
+    let offerId: BN;
+    const newTokenBWantedAmount = tokenBWantedAmount.muln(2);
+
+    it("Lets Alice reprice an open offer and top up the vault", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      accounts.offer = offer;
+      accounts.vault = vault;
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateOffer(
+            offerId,
+            newTokenBWantedAmount,
+            tokenAOfferedAmount,
+            new BN(0)
+          )
+          .accounts({ ...accounts, market: null })
+          .signers([alice])
+          .rpc()
+      );
+
+      const vaultBalance = new BN(
+        (await connection.getTokenAccountBalance(vault)).value.amount
+      );
+      assert(vaultBalance.eq(tokenAOfferedAmount.muln(2)));
+
+      const offerAccount = await program.account.offer.fetch(offer);
+      assert(offerAccount.tokenBWantedAmount.eq(newTokenBWantedAmount));
+      assert(
+        offerAccount.tokenARemainingAmount.eq(tokenAOfferedAmount.muln(2))
+      );
+      assert.equal(offerAccount.revision, 1);
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Rejects a take quoted against a stale revision", async () => {
+      try {
+        await program.methods
+          .takeOffer(newTokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "OfferRevisionMismatch");
+      }
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(newTokenBWantedAmount, [], 1, new BN(0), U64_MAX, 0)
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      assert.isNull(await connection.getAccountInfo(accounts.offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试接受者滑点保护
+  describe("slippage protection", async () => {
+    let offerId: BN;
+
+    it("Rejects a take that would pay or receive outside the taker's limits", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      accounts.offer = offer;
+      accounts.vault = vault;
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      try {
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            tokenAOfferedAmount.addn(1),
+            U64_MAX,
+            0
+          )
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "TokenAAmountTooLow");
+      }
+
+      try {
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            new BN(0),
+            tokenBWantedAmount.subn(1),
+            0
+          )
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "TokenBAmountTooHigh");
+      }
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            0
+          )
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      assert.isNull(await connection.getAccountInfo(offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试荷兰拍卖报价
+  describe("dutch auctions", async () => {
+    let offerId: BN;
+    const startTokenBWantedAmount = tokenBWantedAmount.muln(2);
+
+    it("Lets Bob take an auction at its floor once the price has decayed", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      accounts.offer = offer;
+      accounts.vault = vault;
+
+      // 拍卖已经结束降价，当前价格即为底价
+      const now = Math.floor(Date.now() / SECONDS);
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            startTokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            {
+              startTime: new BN(now - 120),
+              endTime: new BN(now - 60),
+              floorTokenBAmount: tokenBWantedAmount,
+            },
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            0
+          )
+          .accounts({ ...accounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const aliceTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountBBalanceAfter
+          .sub(aliceTokenAccountBBalanceBefore)
+          .eq(tokenBWantedAmount)
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试交易对市场与最优价格成交
+  describe("market", async () => {
+    let cheapOfferId: BN;
+    let priceyOfferId: BN;
+
+    const offerAndVault = (offerId: BN) => {
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      return { offer, vault };
+    };
+
+    it("Keeps listed offers sorted by price", async () => {
+      const market = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          accounts.tokenMintA.toBuffer(),
+          accounts.tokenMintB.toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .createMarket()
+          .accounts({
+            payer: payer.publicKey,
+            tokenMintA: accounts.tokenMintA,
+            tokenMintB: accounts.tokenMintB,
+          })
+          .rpc()
+      );
+
+      // 先上架价格较高的报价，再上架价格较低的报价
+      priceyOfferId = await nextOfferId(accounts.maker);
+      cheapOfferId = priceyOfferId.addn(1);
+      for (const [offerId, wantedAmount] of [
+        [priceyOfferId, tokenBWantedAmount.muln(2)],
+        [cheapOfferId, tokenBWantedAmount],
+      ]) {
+        const { offer, vault } = offerAndVault(offerId);
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .makeOffer(
+              tokenAOfferedAmount,
+              wantedAmount,
+              null,
+              null,
+              null,
+              [],
+              [],
+              null,
+              null,
+              null,
+              null
+            )
+            .accounts({ ...accounts, offer, vault })
+            .signers([alice])
+            .rpc()
+        );
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .listOffer()
+            .accounts({ maker: alice.publicKey, offer })
+            .signers([alice])
+            .rpc()
+        );
+      }
+
+      const marketAccount = await program.account.market.fetch(market);
+      assert.equal(marketAccount.offers.length, 2);
+      assert(
+        marketAccount.offers[0].offer.equals(offerAndVault(cheapOfferId).offer)
+      );
+      assert(
+        marketAccount.offers[1].offer.equals(offerAndVault(priceyOfferId).offer)
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Fills the cheapest offers first up to Bob's budget", async () => {
+      const tokenBBudget = tokenBWantedAmount.muln(2);
+      const remainingAccounts = [cheapOfferId, priceyOfferId].flatMap(
+        (offerId) => {
+          const { offer, vault } = offerAndVault(offerId);
+          return [
+            { pubkey: offer, isSigner: false, isWritable: true },
+            { pubkey: accounts.maker, isSigner: false, isWritable: true },
+            { pubkey: vault, isSigner: false, isWritable: true },
+            {
+              pubkey: accounts.makerTokenAccountB,
+              isSigner: false,
+              isWritable: true,
+            },
+            {
+              pubkey: makerProfileAddress(accounts.maker),
+              isSigner: false,
+              isWritable: true,
+            },
+          ];
+        }
+      );
+
+      const bobTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeBest(tokenBBudget, new BN(0), 2)
+          .accounts({ ...accounts })
+          .remainingAccounts(remainingAccounts)
+          .signers([bob])
+          .rpc()
+      );
+
+      // 低价报价全部成交，高价报价成交一半
+      const bobTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        bobTokenAccountABalanceAfter
+          .sub(bobTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount.add(tokenAOfferedAmount.divn(2)))
+      );
+      assert.isNull(
+        await connection.getAccountInfo(offerAndVault(cheapOfferId).offer)
+      );
+
+      const market = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          accounts.tokenMintA.toBuffer(),
+          accounts.tokenMintB.toBuffer(),
+        ],
+        program.programId
+      )[0];
+      const marketAccount = await program.account.market.fetch(market);
+      assert.equal(marketAccount.offers.length, 1);
+      assert(
+        marketAccount.offers[0].offer.equals(offerAndVault(priceyOfferId).offer)
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Re-sorts a listed offer when Alice reprices it", async () => {
+      const market = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          accounts.tokenMintA.toBuffer(),
+          accounts.tokenMintB.toBuffer(),
+        ],
+        program.programId
+      )[0];
+      const offerId = await nextOfferId(accounts.maker);
+      const { offer, vault } = offerAndVault(offerId);
+
+      // 新报价比剩余的高价报价更便宜，上架后排在最前
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount.muln(3).divn(2),
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts, offer, vault })
+          .signers([alice])
+          .rpc()
+      );
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .listOffer()
+          .accounts({ maker: alice.publicKey, offer })
+          .signers([alice])
+          .rpc()
+      );
+      let marketAccount = await program.account.market.fetch(market);
+      assert(marketAccount.offers[0].offer.equals(offer));
+
+      // 提价后按新价格重新排序，并发出修改事件
+      const newTokenBWantedAmount = tokenBWantedAmount.muln(5).divn(2);
+      const updateSignature = await program.methods
+        .updateOffer(offerId, newTokenBWantedAmount, new BN(0), new BN(0))
+        .accounts({ ...accounts, offer, vault, market })
+        .signers([alice])
+        .rpc();
+      await confirmTransaction(connection, updateSignature);
+
+      marketAccount = await program.account.market.fetch(market);
+      assert.equal(marketAccount.offers.length, 2);
+      assert(
+        marketAccount.offers[0].offer.equals(offerAndVault(priceyOfferId).offer)
+      );
+      assert(marketAccount.offers[1].offer.equals(offer));
+      assert.equal(marketAccount.offers[1].revision, 1);
+      assert(marketAccount.offers[1].tokenBAmount.eq(newTokenBWantedAmount));
+
+      const [updated] = await emittedEvents(updateSignature);
+      assert.equal(updated.name, "offerUpdated");
+      assert(updated.data.offerId.eq(offerId));
+      assert.equal(updated.data.revision, 1);
+      assert(updated.data.tokenARemainingAmount.eq(tokenAOfferedAmount));
+      assert(updated.data.tokenBWantedAmount.eq(newTokenBWantedAmount));
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .unlistOffer()
+          .accounts({ maker: alice.publicKey, offer })
+          .signers([alice])
+          .rpc()
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    describe("full market", async () => {
+      const market = () =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("market"),
+            accounts.tokenMintA.toBuffer(),
+            accounts.tokenMintB.toBuffer(),
+          ],
+          program.programId
+        )[0];
+      const smallAmount = new BN(1_000);
+      let cheapestOfferId: BN;
+
+      const makeOffer = async (wantedAmount: BN) => {
+        const offerId = await nextOfferId(accounts.maker);
+        const { offer, vault } = offerAndVault(offerId);
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .makeOffer(
+              smallAmount,
+              wantedAmount,
+              null,
+              null,
+              null,
+              [],
+              [],
+              null,
+              null,
+              null,
+              null
+            )
+            .accounts({ ...accounts, offer, vault })
+            .signers([alice])
+            .rpc()
+        );
+        return offerId;
+      };
+
+      const listOffer = async (offerId: BN) =>
+        confirmTransaction(
+          connection,
+          await program.methods
+            .listOffer()
+            .accounts({
+              maker: alice.publicKey,
+              offer: offerAndVault(offerId).offer,
+            })
+            .signers([alice])
+            .rpc()
+        );
+
+      it("Evicts the most expensive offer when a cheaper one arrives", async () => {
+        // 以比市场中现有报价更高的价格填满市场
+        let lastOfferId: BN;
+        const marketBefore = await program.account.market.fetch(market());
+        for (
+          let index = marketBefore.offers.length;
+          index < MAX_MARKET_OFFERS;
+          index++
+        ) {
+          lastOfferId = await makeOffer(smallAmount.muln(3));
+          await listOffer(lastOfferId);
+        }
+
+        // 价格更高的报价无法挤进已满的市场
+        const priciestOfferId = await makeOffer(smallAmount.muln(4));
+        try {
+          await listOffer(priciestOfferId);
+          assert.fail("Expected listOffer to throw an error");
+        } catch (error) {
+          assert.include(error.toString(), "MarketFull");
+        }
+
+        // 更便宜的报价挤出价格最高、最晚上架的报价
+        cheapestOfferId = await makeOffer(smallAmount);
+        await listOffer(cheapestOfferId);
+
+        const marketAccount = await program.account.market.fetch(market());
+        assert.equal(marketAccount.offers.length, MAX_MARKET_OFFERS);
+        assert(
+          marketAccount.offers[0].offer.equals(
+            offerAndVault(cheapestOfferId).offer
+          )
+        );
+        assert(
+          marketAccount.offers.every(
+            (entry) => !entry.offer.equals(offerAndVault(lastOfferId).offer)
+          )
+        );
+      }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+      it("Lets anyone prune cancelled offers from the market", async () => {
+        const { offer, vault } = offerAndVault(cheapestOfferId);
+
+        // 仍然有效的报价不能被清理
+        try {
+          await program.methods
+            .pruneMarket()
+            .accounts({ market: market() })
+            .remainingAccounts([
+              { pubkey: offer, isSigner: false, isWritable: false },
+            ])
+            .signers([bob])
+            .rpc();
+          assert.fail("Expected pruneMarket to throw an error");
+        } catch (error) {
+          assert.include(error.toString(), "NothingToPrune");
+        }
+
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .cancelOffer(cheapestOfferId)
+            .accounts({ ...accounts, offer, vault })
+            .signers([alice])
+            .rpc()
+        );
+
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .pruneMarket()
+            .accounts({ market: market() })
+            .remainingAccounts([
+              { pubkey: offer, isSigner: false, isWritable: false },
+            ])
+            .signers([bob])
+            .rpc()
+        );
+
+        const marketAccount = await program.account.market.fetch(market());
+        assert.equal(marketAccount.offers.length, MAX_MARKET_OFFERS - 1);
+        assert(
+          marketAccount.offers.every((entry) => !entry.offer.equals(offer))
+        );
+      }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+    });
+  });
+
+  // 测试原生SOL报价
+  describe("native SOL", async () => {
+    const solAmount = new BN(LAMPORTS_PER_SOL / 10);
+
+    it("Lets Bob pay native SOL for Alice's tokens without wrapping", async () => {
+      const offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      // 代币B为原生SOL，不需要任何代币B账户
+      const solAccounts = {
+        ...accounts,
+        offer,
+        vault,
+        tokenMintB: NATIVE_MINT,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+        makerTokenAccountB: null,
+        takerTokenAccountB: null,
+        feeTokenAccount: null,
+        feeRecipient: null,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            solAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts(solAccounts)
+          .signers([alice])
+          .rpc()
+      );
+
+      // Bob收到的托管账户租金不能抵扣支付的SOL，滑点上限按实际支付的数量检查
+      try {
+        await program.methods
+          .takeOffer(
+            solAmount,
+            [],
+            0,
+            tokenAOfferedAmount,
+            solAmount.subn(1),
+            0
+          )
+          .accounts(solAccounts)
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "TokenBAmountTooHigh");
+      }
+
+      const aliceLamportsBefore = await connection.getBalance(alice.publicKey);
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(solAmount, [], 0, tokenAOfferedAmount, solAmount, 0)
+          .accounts(solAccounts)
+          .signers([bob])
+          .rpc()
+      );
+
+      // Alice收到SOL以及报价账户关闭返还的租金
+      const aliceLamportsAfter = await connection.getBalance(alice.publicKey);
+      assert.isAtLeast(
+        aliceLamportsAfter - aliceLamportsBefore,
+        solAmount.toNumber()
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Escrows native SOL in the offer account and refunds it on cancel", async () => {
+      const offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      // 代币A为原生SOL，lamports直接托管在报价账户中，没有托管代币账户
+      const solAccounts = {
+        ...accounts,
+        offer,
+        tokenMintA: NATIVE_MINT,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        makerTokenAccountA: null,
+        vault: null,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            solAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts(solAccounts)
+          .signers([alice])
+          .rpc()
+      );
+
+      const offerAccount = await program.account.offer.fetch(offer);
+      assert(offerAccount.tokenAOfferedAmount.eq(solAmount));
+
+      const offerLamportsBefore = await connection.getBalance(offer);
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .cancelOffer(offerId)
+          .accounts(solAccounts)
+          .signers([alice])
+          .rpc()
+      );
+
+      // 报价账户只剩租金
+      const offerLamportsAfter = await connection.getBalance(offer);
+      assert.equal(
+        offerLamportsBefore - offerLamportsAfter,
+        solAmount.toNumber()
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试批量成交多个报价
+  describe("take offers", async () => {
+    it("Sweeps several offers in one transaction within Bob's limits", async () => {
+      const firstOfferId = await nextOfferId(accounts.maker);
+      const offerIds = [firstOfferId, firstOfferId.addn(1)];
+      const offers = offerIds.map((offerId) => {
+        const offer = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("offer"),
+            accounts.maker.toBuffer(),
+            offerId.toArrayLike(Buffer, "le", 8),
+          ],
+          program.programId
+        )[0];
+        const vault = getAssociatedTokenAddressSync(
+          accounts.tokenMintA,
+          offer,
+          true,
+          TOKEN_PROGRAM
+        );
+        return { offer, vault };
+      });
+
+      for (const { offer, vault } of offers) {
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .makeOffer(
+              tokenAOfferedAmount,
+              tokenBWantedAmount,
+              null,
+              null,
+              null,
+              [],
+              [],
+              null,
+              null,
+              null,
+              null
+            )
+            .accounts({ ...accounts, offer, vault })
+            .signers([alice])
+            .rpc()
+        );
+      }
+
+      const remainingAccounts = offers.flatMap(({ offer, vault }) => [
+        { pubkey: offer, isSigner: false, isWritable: true },
+        { pubkey: accounts.maker, isSigner: false, isWritable: true },
+        { pubkey: vault, isSigner: false, isWritable: true },
+        {
+          pubkey: accounts.makerTokenAccountB,
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: makerProfileAddress(accounts.maker),
+          isSigner: false,
+          isWritable: true,
+        },
+      ]);
+      const fills = [tokenBWantedAmount, tokenBWantedAmount];
+      const totalTokenA = tokenAOfferedAmount.muln(2);
+      const totalTokenB = tokenBWantedAmount.muln(2);
+
+      // 总支付上限低于所需数量时整笔交易失败
+      try {
+        await program.methods
+          .takeOffers(fills, totalTokenA, totalTokenB.subn(1))
+          .accounts({ ...accounts })
+          .remainingAccounts(remainingAccounts)
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffers to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "TokenBAmountTooHigh");
+      }
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffers(fills, totalTokenA, totalTokenB)
+          .accounts({ ...accounts })
+          .remainingAccounts(remainingAccounts)
+          .signers([bob])
+          .rpc()
+      );
+
+      for (const { offer, vault } of offers) {
+        assert.isNull(await connection.getAccountInfo(offer));
+        assert.isNull(await connection.getAccountInfo(vault));
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Leaves merkle-gated offers to take_offer", async () => {
+      const offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            Array.from(randomBytes(32)),
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts, offer, vault })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 批量成交无法提交接受者的默克尔证明，这类报价直接被拒绝
+      try {
+        await program.methods
+          .takeOffers([tokenBWantedAmount], new BN(0), U64_MAX)
+          .accounts({ ...accounts })
+          .remainingAccounts([
+            { pubkey: offer, isSigner: false, isWritable: true },
+            { pubkey: accounts.maker, isSigner: false, isWritable: true },
+            { pubkey: vault, isSigner: false, isWritable: true },
+            {
+              pubkey: accounts.makerTokenAccountB,
+              isSigner: false,
+              isWritable: true,
+            },
+            {
+              pubkey: makerProfileAddress(accounts.maker),
+              isSigner: false,
+              isWritable: true,
+            },
+          ])
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffers to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "OfferNotRoutable");
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  describe("signed orders", async () => {
+    const nonce = getRandomBigNumber();
+
+    // 签名消息为域标签 + 程序ID + 订单的Borsh序列化结果
+    const signedOrderMessage = (order: object) =>
+      Buffer.concat([
+        Buffer.from("swap:signed-order"),
+        program.programId.toBuffer(),
+        program.coder.types.encode("SignedOrder", order),
+      ]);
+
+    const nonceAddress = (orderNonce: BN) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("nonce"),
+          accounts.maker.toBuffer(),
+          orderNonce.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+    it("Fills Alice's signed order from her delegated token account", async () => {
+      const delegate = PublicKey.findProgramAddressSync(
+        [Buffer.from("delegate"), accounts.maker.toBuffer()],
+        program.programId
+      )[0];
+      const nonceAccount = nonceAddress(nonce);
+
+      // Alice只需授权额度，不需要把代币A托管到资金库
+      await approve(
+        connection,
+        payer,
+        accounts.makerTokenAccountA,
+        delegate,
+        alice,
+        BigInt(tokenAOfferedAmount.toString()),
+        [],
+        undefined,
+        TOKEN_PROGRAM
+      );
+
+      const order = {
+        maker: accounts.maker,
+        tokenMintA: accounts.tokenMintA,
+        tokenMintB: accounts.tokenMintB,
+        tokenAAmount: tokenAOfferedAmount,
+        tokenBAmount: tokenBWantedAmount,
+        nonce,
+        expiresAt: new BN(Math.floor(Date.now() / SECONDS) + 60),
+      };
+      const signatureInstruction =
+        Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: alice.secretKey,
+          message: signedOrderMessage(order),
+        });
+      const signedOrderAccounts = {
+        ...accounts,
+        nonce: nonceAccount,
+      };
+
+      // 篡改订单数量后签名不再匹配
+      try {
+        await program.methods
+          .fillSignedOrder({
+            ...order,
+            tokenAAmount: order.tokenAAmount.muln(2),
+          })
+          .accounts(signedOrderAccounts)
+          .preInstructions([signatureInstruction])
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected fillSignedOrder to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidOrderSignature");
+      }
+
+      const bobTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .fillSignedOrder(order)
+          .accounts(signedOrderAccounts)
+          .preInstructions([signatureInstruction])
+          .signers([bob])
+          .rpc()
+      );
+
+      const bobTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        bobTokenAccountABalanceAfter
+          .sub(bobTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount)
+      );
+
+      // 同一随机数的订单不能重复成交
+      try {
+        await program.methods
+          .fillSignedOrder(order)
+          .accounts(signedOrderAccounts)
+          .preInstructions([signatureInstruction])
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected fillSignedOrder to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "already in use");
+      }
+
+      // 订单过期前Bob不能关闭随机数账户取回租金
+      try {
+        await program.methods
+          .closeOrderNonce()
+          .accounts({ payer: bob.publicKey, nonce: nonceAccount })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected closeOrderNonce to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "OrderNotExpired");
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Delivers the full signed amount when token A charges a transfer fee", async () => {
+      // 创建带1%转账手续费的代币A，并铸造给Alice
+      const [feeMint] = makeKeypairs(1);
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: payer.publicKey,
+            newAccountPubkey: feeMint.publicKey,
+            space: mintLen,
+            lamports: await connection.getMinimumBalanceForRentExemption(
+              mintLen
+            ),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            feeMint.publicKey,
+            payer.publicKey,
+            payer.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(
+            feeMint.publicKey,
+            6,
+            payer.publicKey,
+            null,
+            TOKEN_2022_PROGRAM_ID
+          )
+        ),
+        [payer, feeMint]
+      );
+      const aliceFeeMintTokenAccount =
+        await createAssociatedTokenAccountIdempotent(
+          connection,
+          payer,
+          feeMint.publicKey,
+          alice.publicKey,
+          {},
+          TOKEN_2022_PROGRAM_ID
+        );
+      await mintTo(
+        connection,
+        payer,
+        feeMint.publicKey,
+        aliceFeeMintTokenAccount,
+        payer,
+        1_000_000_000,
+        [],
+        {},
+        TOKEN_2022_PROGRAM_ID
+      );
+      const bobFeeMintTokenAccount = getAssociatedTokenAddressSync(
+        feeMint.publicKey,
+        bob.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      // 授权额度需覆盖由Alice承担的转账手续费
+      const delegate = PublicKey.findProgramAddressSync(
+        [Buffer.from("delegate"), accounts.maker.toBuffer()],
+        program.programId
+      )[0];
+      await approve(
+        connection,
+        payer,
+        aliceFeeMintTokenAccount,
+        delegate,
+        alice,
+        BigInt(tokenAOfferedAmount.muln(2).toString()),
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const order = {
+        maker: accounts.maker,
+        tokenMintA: feeMint.publicKey,
+        tokenMintB: accounts.tokenMintB,
+        tokenAAmount: tokenAOfferedAmount,
+        tokenBAmount: tokenBWantedAmount,
+        nonce: getRandomBigNumber(),
+        expiresAt: new BN(Math.floor(Date.now() / SECONDS) + 60),
+      };
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .fillSignedOrder(order)
+          .accounts({
+            ...accounts,
+            tokenMintA: feeMint.publicKey,
+            tokenProgramA: TOKEN_2022_PROGRAM_ID,
+            makerTokenAccountA: aliceFeeMintTokenAccount,
+            takerTokenAccountA: bobFeeMintTokenAccount,
+            nonce: nonceAddress(order.nonce),
+          })
+          .preInstructions([
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: alice.secretKey,
+              message: signedOrderMessage(order),
+            }),
+          ])
+          .signers([bob])
+          .rpc()
+      );
+
+      // Bob按订单数量足额收到代币A，Alice额外承担了转账手续费
+      const bobFeeMintBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(bobFeeMintTokenAccount)
+        ).value.amount
+      );
+      assert(bobFeeMintBalance.eq(tokenAOfferedAmount));
+      const aliceFeeMintBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(aliceFeeMintTokenAccount)
+        ).value.amount
+      );
+      assert(
+        aliceFeeMintBalance.lt(new BN(1_000_000_000).sub(tokenAOfferedAmount))
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Rejects orders signed without the domain tag", async () => {
+      const order = {
+        maker: accounts.maker,
+        tokenMintA: accounts.tokenMintA,
+        tokenMintB: accounts.tokenMintB,
+        tokenAAmount: tokenAOfferedAmount,
+        tokenBAmount: tokenBWantedAmount,
+        nonce: getRandomBigNumber(),
+        expiresAt: new BN(Math.floor(Date.now() / SECONDS) + 60),
+      };
+
+      try {
+        await program.methods
+          .fillSignedOrder(order)
+          .accounts({ ...accounts, nonce: nonceAddress(order.nonce) })
+          .preInstructions([
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: alice.secretKey,
+              message: program.coder.types.encode("SignedOrder", order),
+            }),
+          ])
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected fillSignedOrder to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidOrderSignature");
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Lets Alice cancel a signed order and reclaim the nonce rent after it expires", async () => {
+      const order = {
+        maker: accounts.maker,
+        tokenMintA: accounts.tokenMintA,
+        tokenMintB: accounts.tokenMintB,
+        tokenAAmount: tokenAOfferedAmount,
+        tokenBAmount: tokenBWantedAmount,
+        nonce: getRandomBigNumber(),
+        expiresAt: new BN(Math.floor(Date.now() / SECONDS) + 3),
+      };
+      const nonceAccount = nonceAddress(order.nonce);
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .cancelOrderNonce(order.nonce, order.expiresAt)
+          .accounts({ maker: alice.publicKey })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 已取消的订单即使签名有效也不能成交
+      try {
+        await program.methods
+          .fillSignedOrder(order)
+          .accounts({ ...accounts, nonce: nonceAccount })
+          .preInstructions([
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: alice.secretKey,
+              message: signedOrderMessage(order),
+            }),
+          ])
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected fillSignedOrder to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "already in use");
+      }
+
+      // 等待订单过期后关闭随机数账户
+      await new Promise((resolve) => setTimeout(resolve, 6 * SECONDS));
+
+      // 只有支付租金的Alice可以关闭
+      try {
+        await program.methods
+          .closeOrderNonce()
+          .accounts({ payer: bob.publicKey, nonce: nonceAccount })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected closeOrderNonce to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "ConstraintHasOne");
+      }
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .closeOrderNonce()
+          .accounts({ payer: alice.publicKey, nonce: nonceAccount })
+          .signers([alice])
+          .rpc()
+      );
+      assert.isNull(await connection.getAccountInfo(nonceAccount));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  describe("referral fee", async () => {
+    let offerId: BN;
+    const maxReferralBps = 100;
+    const referralBps = 50;
+
+    it("Charges the referrer's cut to Bob on top of Alice's amount", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, null, null, null, maxReferralBps, null)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      const [referrer] = makeKeypairs(1);
+      const referrerTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        accounts.tokenMintB,
+        referrer.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const referralAccounts = {
+        ...accounts,
+        offer,
+        vault,
+        referrerTokenAccount,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...referralAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 推荐费率超过配置上限时交易失败
+      try {
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            new BN(0),
+            U64_MAX,
+            maxReferralBps + 1
+          )
+          .accounts({ ...referralAccounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "ReferralBpsTooHigh");
+      }
+
+      const aliceTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+      const bobTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountB)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, referralBps)
+          .accounts({ ...referralAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const expectedReferralFee = tokenBWantedAmount
+        .muln(referralBps)
+        .divn(10_000);
+      const bobTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountB)
+        ).value.amount
+      );
+      assert(
+        bobTokenAccountBBalanceBefore
+          .sub(bobTokenAccountBBalanceAfter)
+          .eq(tokenBWantedAmount.add(expectedReferralFee))
+      );
+
+      const referrerBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(referrerTokenAccount)
+        ).value.amount
+      );
+      const aliceTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+
+      assert(referrerBalance.eq(expectedReferralFee));
+      assert(
+        aliceTokenAccountBBalanceAfter
+          .sub(aliceTokenAccountBBalanceBefore)
+          .eq(tokenBWantedAmount)
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Does not let Bob refer himself at Alice's expense", async () => {
+      const selfReferralOfferId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          selfReferralOfferId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      // Bob把自己控制的另一个钱包的代币B账户作为推荐者账户
+      const [bobReferrer] = makeKeypairs(1);
+      const selfReferralAccounts = {
+        ...accounts,
+        offer,
+        vault,
+        referrerTokenAccount: await createAssociatedTokenAccountIdempotent(
+          connection,
+          payer,
+          accounts.tokenMintB,
+          bobReferrer.publicKey,
+          {},
+          TOKEN_PROGRAM
+        ),
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...selfReferralAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+      const bobTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountB)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            new BN(0),
+            U64_MAX,
+            maxReferralBps
+          )
+          .accounts({ ...selfReferralAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      // Alice足额收到想要的代币B，推荐费完全由Bob自己承担
+      const aliceTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+      const bobTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountB)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountBBalanceAfter
+          .sub(aliceTokenAccountBBalanceBefore)
+          .eq(tokenBWantedAmount)
+      );
+      assert(
+        bobTokenAccountBBalanceBefore
+          .sub(bobTokenAccountBBalanceAfter)
+          .eq(
+            tokenBWantedAmount.add(
+              tokenBWantedAmount.muln(maxReferralBps).divn(10_000)
+            )
+          )
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  describe("NFT royalties", async () => {
+    let offerId: BN;
+    const METADATA_PROGRAM_ID = new PublicKey(
+      "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+    );
+
+    it("Requires the metadata account when token A is an NFT", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      // 精度为0且只铸造1个的代币视为NFT
+      const nftMint = await createMint(
+        connection,
+        payer,
+        payer.publicKey,
+        null,
+        0,
+        undefined,
+        {},
+        TOKEN_PROGRAM
+      );
+      const aliceNftAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        nftMint,
+        alice.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+      await mintTo(
+        connection,
+        payer,
+        nftMint,
+        aliceNftAccount,
+        payer,
+        1,
+        [],
+        {},
+        TOKEN_PROGRAM
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        nftMint,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const nftMetadata = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("metadata"),
+          METADATA_PROGRAM_ID.toBuffer(),
+          nftMint.toBuffer(),
+        ],
+        METADATA_PROGRAM_ID
+      )[0];
+
+      const nftAccounts = {
+        ...accounts,
+        tokenMintA: nftMint,
+        makerTokenAccountA: aliceNftAccount,
+        takerTokenAccountA: getAssociatedTokenAddressSync(
+          nftMint,
+          bob.publicKey,
+          false,
+          TOKEN_PROGRAM
+        ),
+        offer,
+        vault,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            new BN(1),
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...nftAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 不提供元数据账户就无法绕过版税
+      try {
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...nftAccounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "MissingMetadataAccount");
+      }
+
+      const aliceTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+
+      // 没有Metaplex元数据的NFT不收取版税
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(1), U64_MAX, 0)
+          .accounts({ ...nftAccounts, nftMetadata })
+          .signers([bob])
+          .rpc()
+      );
+
+      const aliceTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountBBalanceAfter
+          .sub(aliceTokenAccountBBalanceBefore)
+          .eq(tokenBWantedAmount)
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    // 铸造一个只属于Alice的NFT（精度为0且只铸造1个）
+    const createNft = async (tokenProgram: PublicKey) => {
+      const nftMint = await createMint(
+        connection,
+        payer,
+        payer.publicKey,
+        null,
+        0,
+        undefined,
+        {},
+        tokenProgram
+      );
+      const aliceNftAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        nftMint,
+        alice.publicKey,
+        {},
+        tokenProgram
+      );
+      await mintTo(
+        connection,
+        payer,
+        nftMint,
+        aliceNftAccount,
+        payer,
+        1,
+        [],
+        {},
+        tokenProgram
+      );
+      return { nftMint, aliceNftAccount };
+    };
+
+    const borshString = (value: string) => {
+      const bytes = Buffer.from(value);
+      const length = Buffer.alloc(4);
+      length.writeUInt32LE(bytes.length);
+      return Buffer.concat([length, bytes]);
+    };
+
+    // Token Metadata的CreateMetadataAccountV3指令，
+    // 签名的更新权限可以将自己标记为已验证创作者
+    const createMetadataInstruction = (
+      nftMint: PublicKey,
+      nftMetadata: PublicKey,
+      updateAuthority: PublicKey,
+      sellerFeeBasisPoints: number,
+      creators: Array<{ address: PublicKey; verified: boolean; share: number }>
+    ) => {
+      const feeBasisPoints = Buffer.alloc(2);
+      feeBasisPoints.writeUInt16LE(sellerFeeBasisPoints);
+      const creatorCount = Buffer.alloc(4);
+      creatorCount.writeUInt32LE(creators.length);
+
+      return new TransactionInstruction({
+        programId: METADATA_PROGRAM_ID,
+        keys: [
+          { pubkey: nftMetadata, isSigner: false, isWritable: true },
+          { pubkey: nftMint, isSigner: false, isWritable: false },
+          { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+          { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+          { pubkey: updateAuthority, isSigner: true, isWritable: false },
+          {
+            pubkey: SystemProgram.programId,
+            isSigner: false,
+            isWritable: false,
+          },
+        ],
+        data: Buffer.concat([
+          // CreateMetadataAccountV3
+          Buffer.from([33]),
+          borshString("Swap NFT"),
+          borshString("SNFT"),
+          borshString(""),
+          feeBasisPoints,
+          Buffer.from([1]),
+          creatorCount,
+          ...creators.map((creator) =>
+            Buffer.concat([
+              creator.address.toBuffer(),
+              Buffer.from([creator.verified ? 1 : 0, creator.share]),
+            ])
+          ),
+          // collection、uses为空，元数据可修改，collection_details为空
+          Buffer.from([0, 0, 1, 0]),
+        ]),
+      });
+    };
+
+    it("Pays verified creators their share of the royalty out of Bob's payment", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      // Token Metadata程序只支持经典代币程序的Mint
+      const { nftMint, aliceNftAccount } = await createNft(TOKEN_PROGRAM_ID);
+      const [creator, unverifiedCreator] = makeKeypairs(2);
+      const sellerFeeBasisPoints = 500;
+
+      const nftMetadata = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("metadata"),
+          METADATA_PROGRAM_ID.toBuffer(),
+          nftMint.toBuffer(),
+        ],
+        METADATA_PROGRAM_ID
+      )[0];
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          createMetadataInstruction(
+            nftMint,
+            nftMetadata,
+            creator.publicKey,
+            sellerFeeBasisPoints,
+            [
+              { address: creator.publicKey, verified: true, share: 60 },
+              {
+                address: unverifiedCreator.publicKey,
+                verified: false,
+                share: 40,
+              },
+            ]
+          )
+        ),
+        [payer, creator]
+      );
+
+      const creatorTokenAccountB = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        accounts.tokenMintB,
+        creator.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const nftAccounts = {
+        ...accounts,
+        tokenMintA: nftMint,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        makerTokenAccountA: aliceNftAccount,
+        takerTokenAccountA: getAssociatedTokenAddressSync(
+          nftMint,
+          bob.publicKey,
+          false,
+          TOKEN_PROGRAM_ID
+        ),
+        offer,
+        vault: getAssociatedTokenAddressSync(
+          nftMint,
+          offer,
+          true,
+          TOKEN_PROGRAM_ID
+        ),
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            new BN(1),
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...nftAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceTokenAccountBBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(1), U64_MAX, 0)
+          .accounts({ ...nftAccounts, nftMetadata })
+          .remainingAccounts([
+            { pubkey: creatorTokenAccountB, isSigner: false, isWritable: true },
+          ])
+          .signers([bob])
+          .rpc()
+      );
+
+      // 版税为支付数量的5%，只有已验证创作者按60%的份额收到
+      const creatorRoyalty = tokenBWantedAmount
+        .muln(sellerFeeBasisPoints)
+        .divn(10_000)
+        .muln(60)
+        .divn(100);
+      const creatorBalance = new BN(
+        (await connection.getTokenAccountBalance(creatorTokenAccountB)).value
+          .amount
+      );
+      assert(creatorBalance.eq(creatorRoyalty));
+
+      const aliceTokenAccountBBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountB)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountBBalanceAfter
+          .sub(aliceTokenAccountBBalanceBefore)
+          .eq(tokenBWantedAmount.sub(creatorRoyalty))
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Rejects NFTs as basket legs, which would skip royalties", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const { nftMint, aliceNftAccount } = await createNft(TOKEN_PROGRAM);
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const nftVault = getAssociatedTokenAddressSync(
+        nftMint,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      try {
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [{ mint: nftMint, amount: new BN(1) }],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts, offer, vault })
+          .remainingAccounts([
+            { pubkey: nftMint, isSigner: false, isWritable: false },
+            { pubkey: aliceNftAccount, isSigner: false, isWritable: true },
+            { pubkey: nftVault, isSigner: false, isWritable: true },
+          ])
+          .signers([alice])
+          .rpc();
+        assert.fail("Expected makeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "NftBasketLeg");
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  describe("vesting offers", async () => {
+    let offerId: BN;
+
+    it("Locks token A after the fill and unlocks it to Bob over time", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const vestingAccounts = { ...accounts, offer, vault };
+      const now = Math.floor(Date.now() / SECONDS);
+      const vesting = { startTime: new BN(now), endTime: new BN(now + 4) };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            vesting,
+            null,
+            null
+          )
+          .accounts({ ...vestingAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 锁定的代币A计入接受者的滑点检查
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(
+            tokenBWantedAmount,
+            [],
+            0,
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            0
+          )
+          .accounts({ ...vestingAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const offerAccount = await program.account.offer.fetch(offer);
+      assert(offerAccount.vestingBeneficiary.equals(bob.publicKey));
+      assert(offerAccount.tokenAVestingAmount.eq(tokenAOfferedAmount));
+
+      // 托管的代币A已归属于Bob，Alice不能再取消报价
+      try {
+        await program.methods
+          .cancelOffer(offerId)
+          .accounts({ ...vestingAccounts })
+          .signers([alice])
+          .rpc();
+        assert.fail("Expected cancelOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "OfferIsVesting");
+      }
+
+      const bobTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+
+      // 等待全部解锁后一次提取
+      await new Promise((resolve) => setTimeout(resolve, 6 * SECONDS));
+
+      const claimSignature = await program.methods
+        .claimVested()
+        .accounts({
+          ...vestingAccounts,
+          beneficiary: bob.publicKey,
+          beneficiaryTokenAccountA: accounts.takerTokenAccountA,
+        })
+        .signers([bob])
+        .rpc();
+      await confirmTransaction(connection, claimSignature);
+
+      const [claimed] = await emittedEvents(claimSignature);
+      assert.equal(claimed.name, "vestedTokensClaimed");
+      assert(claimed.data.beneficiary.equals(bob.publicKey));
+      assert(claimed.data.tokenAAmount.eq(tokenAOfferedAmount));
+      assert(claimed.data.tokenAClaimedAmount.eq(tokenAOfferedAmount));
+
+      const bobTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        bobTokenAccountABalanceAfter
+          .sub(bobTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount)
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+      assert.isNull(await connection.getAccountInfo(vault));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  describe("arbitrated offers", async () => {
+    let offerId: BN;
+
+    it("Lets only the arbiter or both parties release the vault to Bob", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const [arbiter] = makeKeypairs(1);
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const arbitratedAccounts = { ...accounts, offer, vault };
+      const disputeDeadline = new BN(Math.floor(Date.now() / SECONDS) + 3600);
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            bob.publicKey,
+            null,
+            [],
+            [],
+            null,
+            null,
+            arbiter.publicKey,
+            disputeDeadline
+          )
+          .accounts({ ...arbitratedAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 代币B在链下结算，不能直接成交
+      try {
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...arbitratedAccounts })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "OfferIsArbitrated");
+      }
+
+      // 接受者不能单方面放款
+      try {
+        await program.methods
+          .releaseToTaker()
+          .accounts({
+            ...arbitratedAccounts,
+            arbiter: null,
+            feeTokenAccount: null,
+          })
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected releaseToTaker to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "NotAuthorizedToResolve");
+      }
+
+      const bobTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+
+      const releaseSignature = await program.methods
+        .releaseToTaker()
+        .accounts({
+          ...arbitratedAccounts,
+          arbiter: arbiter.publicKey,
+          feeTokenAccount: null,
+        })
+        .signers([arbiter])
+        .rpc();
+      await confirmTransaction(connection, releaseSignature);
+
+      const [taken] = await emittedEvents(releaseSignature);
+      assert.equal(taken.name, "offerTaken");
+      assert(taken.data.taker.equals(bob.publicKey));
+      assert(taken.data.tokenAAmount.eq(tokenAOfferedAmount));
+
+      const bobTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        bobTokenAccountABalanceAfter
+          .sub(bobTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount)
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+      assert.isNull(await connection.getAccountInfo(vault));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Charges the protocol fee on token A and honours the pause when releasing", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const [arbiter] = makeKeypairs(1);
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const feeTokenAccountA = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        accounts.tokenMintA,
+        payer.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+      const arbitratedAccounts = {
+        ...accounts,
+        offer,
+        vault,
+        arbiter: arbiter.publicKey,
+        feeTokenAccount: feeTokenAccountA,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            bob.publicKey,
+            null,
+            [],
+            [],
+            null,
+            null,
+            arbiter.publicKey,
+            new BN(Math.floor(Date.now() / SECONDS) + 3600)
+          )
+          .accounts({ ...arbitratedAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const feeBps = 100;
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, feeBps, null, null, null, true)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      // 协议暂停时仲裁人也不能放款
+      try {
+        await program.methods
+          .releaseToTaker()
+          .accounts({ ...arbitratedAccounts })
+          .signers([arbiter])
+          .rpc();
+        assert.fail("Expected releaseToTaker to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "ProtocolPaused");
+      }
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, null, null, null, null, false)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      const feeBalanceBefore = new BN(
+        (await connection.getTokenAccountBalance(feeTokenAccountA)).value.amount
+      );
+      const bobTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .releaseToTaker()
+          .accounts({ ...arbitratedAccounts })
+          .signers([arbiter])
+          .rpc()
+      );
+
+      // 代币B在链下结算，手续费从托管的代币A中扣除
+      const expectedFee = tokenAOfferedAmount.muln(feeBps).divn(10_000);
+
+      const feeBalanceAfter = new BN(
+        (await connection.getTokenAccountBalance(feeTokenAccountA)).value.amount
+      );
+      const bobTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.takerTokenAccountA)
+        ).value.amount
+      );
+      assert(feeBalanceAfter.sub(feeBalanceBefore).eq(expectedFee));
+      assert(
+        bobTokenAccountABalanceAfter
+          .sub(bobTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount.sub(expectedFee))
+      );
+      assert.isNull(await connection.getAccountInfo(vault));
+
+      // 恢复为零手续费，避免影响其他测试
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, 0, null, null, null, null)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Refunds Alice when the arbiter rules for her", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const [arbiter] = makeKeypairs(1);
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const arbitratedAccounts = { ...accounts, offer, vault };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            bob.publicKey,
+            null,
+            [],
+            [],
+            null,
+            null,
+            arbiter.publicKey,
+            new BN(Math.floor(Date.now() / SECONDS) + 3600)
+          )
+          .accounts({ ...arbitratedAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountA)
+        ).value.amount
+      );
+
+      const refundSignature = await program.methods
+        .refundToMaker()
+        .accounts({ ...arbitratedAccounts, arbiter: arbiter.publicKey })
+        .signers([arbiter])
+        .rpc();
+      await confirmTransaction(connection, refundSignature);
+
+      const [cancelled] = await emittedEvents(refundSignature);
+      assert.equal(cancelled.name, "offerCancelled");
+      assert(cancelled.data.offerId.eq(offerId));
+      assert(cancelled.data.tokenARefundedAmount.eq(tokenAOfferedAmount));
+
+      const aliceTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountABalanceAfter
+          .sub(aliceTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount)
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Lets Alice refund alone only after the dispute deadline", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const [arbiter] = makeKeypairs(1);
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const arbitratedAccounts = { ...accounts, offer, vault, arbiter: null };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            bob.publicKey,
+            null,
+            [],
+            [],
+            null,
+            null,
+            arbiter.publicKey,
+            new BN(Math.floor(Date.now() / SECONDS) + 3)
+          )
+          .accounts({ ...arbitratedAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 争议截止时间之前，创建者不能单方面取回
+      try {
+        await program.methods
+          .refundToMaker()
+          .accounts({ ...arbitratedAccounts })
+          .signers([alice])
+          .rpc();
+        assert.fail("Expected refundToMaker to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "NotAuthorizedToResolve");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 5 * SECONDS));
+
+      const aliceTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountA)
+        ).value.amount
+      );
+
+      // 截止时间过后无需仲裁人或Bob签名
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .refundToMaker()
+          .accounts({ ...arbitratedAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountABalanceAfter
+          .sub(aliceTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount)
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试协议暂停与代币名单功能
+  describe("protocol pause and mint list", async () => {
+    const tryMakeOffer = async () => {
+      const offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      await program.methods
+        .makeOffer(
+          tokenAOfferedAmount,
+          tokenBWantedAmount,
+          null,
+          null,
+          null,
+          [],
+          [],
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({ ...accounts, offer, vault })
+        .signers([alice])
+        .rpc();
+    };
+
+    it("Rejects new offers while the protocol is paused", async () => {
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateConfig(null, null, null, null, null, true)
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      try {
+        await tryMakeOffer();
+        assert.fail("Expected makeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "The protocol is paused");
+      } finally {
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .updateConfig(null, null, null, null, null, false)
+            .accounts({ admin: payer.publicKey })
+            .rpc()
+        );
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Rejects offers for a denylisted mint", async () => {
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateMintList({ denylist: {} }, [accounts.tokenMintB])
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      try {
+        await tryMakeOffer();
+        assert.fail("Expected makeOffer to throw an error");
+      } catch (error) {
+        assert.include(
+          error.toString(),
+          "Mint is not allowed by the mint list"
+        );
+      } finally {
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .updateMintList({ disabled: {} }, [])
+            .accounts({ admin: payer.publicKey })
+            .rpc()
+        );
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Blocks fills of basket offers once a leg mint is denylisted", async () => {
+      const extraMint = await createMint(
+        connection,
+        payer,
+        payer.publicKey,
+        null,
+        6,
+        undefined,
+        {},
+        TOKEN_PROGRAM
+      );
+      const aliceExtraTokenAccount = await createAssociatedTokenAccountIdempotent(
+        connection,
+        payer,
+        extraMint,
+        alice.publicKey,
+        {},
+        TOKEN_PROGRAM
+      );
+      await mintTo(
+        connection,
+        payer,
+        extraMint,
+        aliceExtraTokenAccount,
+        payer,
+        1_000_000,
+        [],
+        {},
+        TOKEN_PROGRAM
+      );
+
+      const offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const extraVault = getAssociatedTokenAddressSync(
+        extraMint,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [{ mint: extraMint, amount: new BN(1_000_000) }],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...accounts, offer, vault })
+          .remainingAccounts([
+            { pubkey: extraMint, isSigner: false, isWritable: false },
+            { pubkey: aliceExtraTokenAccount, isSigner: false, isWritable: true },
+            { pubkey: extraVault, isSigner: false, isWritable: true },
+          ])
+          .signers([alice])
+          .rpc()
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateMintList({ denylist: {} }, [extraMint])
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      try {
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...accounts, offer, vault })
+          .remainingAccounts([
+            { pubkey: extraMint, isSigner: false, isWritable: false },
+            { pubkey: extraVault, isSigner: false, isWritable: true },
+            {
+              pubkey: getAssociatedTokenAddressSync(
+                extraMint,
+                bob.publicKey,
+                false,
+                TOKEN_PROGRAM
+              ),
+              isSigner: false,
+              isWritable: true,
+            },
+          ])
+          .signers([bob])
+          .rpc();
+        assert.fail("Expected takeOffer to throw an error");
+      } catch (error) {
+        assert.include(
+          error.toString(),
+          "Mint is not allowed by the mint list"
+        );
+      } finally {
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .updateMintList({ disabled: {} }, [])
+            .accounts({ admin: payer.publicKey })
+            .rpc()
+        );
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Accepts an allowlisted mint that keeps a freeze authority", async () => {
+      // 模拟USDC等保留冻结权限的稳定币
+      const freezableMint = await createMint(
+        connection,
+        payer,
+        payer.publicKey,
+        payer.publicKey,
+        6,
+        undefined,
+        {},
+        TOKEN_PROGRAM
+      );
+      const aliceFreezableTokenAccount =
+        await createAssociatedTokenAccountIdempotent(
+          connection,
+          payer,
+          freezableMint,
+          alice.publicKey,
+          {},
+          TOKEN_PROGRAM
+        );
+      await mintTo(
+        connection,
+        payer,
+        freezableMint,
+        aliceFreezableTokenAccount,
+        payer,
+        tokenAOfferedAmount.toNumber(),
+        [],
+        {},
+        TOKEN_PROGRAM
+      );
+
+      const makeFreezableOffer = async () => {
+        const offerId = await nextOfferId(accounts.maker);
+        const offer = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("offer"),
+            accounts.maker.toBuffer(),
+            offerId.toArrayLike(Buffer, "le", 8),
+          ],
+          program.programId
+        )[0];
+        const vault = getAssociatedTokenAddressSync(
+          freezableMint,
+          offer,
+          true,
+          TOKEN_PROGRAM
+        );
+
+        return program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({
+            ...accounts,
+            tokenMintA: freezableMint,
+            makerTokenAccountA: aliceFreezableTokenAccount,
+            offer,
+            vault,
+          })
+          .signers([alice])
+          .rpc();
+      };
+
+      // 未启用名单时拒绝带冻结权限的代币
+      try {
+        await makeFreezableOffer();
+        assert.fail("Expected makeOffer to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "DangerousMint");
+      }
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateMintList({ allowlist: {} }, [
+            freezableMint,
+            accounts.tokenMintB,
+          ])
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+
+      try {
+        await confirmTransaction(connection, await makeFreezableOffer());
+      } finally {
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .updateMintList({ disabled: {} }, [])
+            .accounts({ admin: payer.publicKey })
+            .rpc()
+        );
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试创建者档案分配报价ID与记录成交统计
+  describe("maker profile", async () => {
+    it("Assigns sequential offer IDs and tracks Alice's open offers and fills", async () => {
+      const makerProfile = makerProfileAddress(accounts.maker);
+      const offerId = await nextOfferId(accounts.maker);
+      const profileBefore = await program.account.makerProfile.fetch(
+        makerProfile
+      );
+
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const profileAccounts = { ...accounts, offer, vault };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...profileAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const offerAccount = await program.account.offer.fetch(offer);
+      assert(offerAccount.offerId.eq(offerId));
+      const profileAfterMake = await program.account.makerProfile.fetch(
+        makerProfile
+      );
+      assert(profileAfterMake.nextOfferId.eq(offerId.addn(1)));
+      assert(
+        profileAfterMake.openOfferCount.eq(
+          profileBefore.openOfferCount.addn(1)
+        )
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts({ ...profileAccounts })
+          .signers([bob])
+          .rpc()
+      );
+
+      const profileAfterTake = await program.account.makerProfile.fetch(
+        makerProfile
+      );
+      assert(profileAfterTake.openOfferCount.eq(profileBefore.openOfferCount));
+      assert(profileAfterTake.fillCount.eq(profileBefore.fillCount.addn(1)));
+      assert(
+        profileAfterTake.filledOfferCount.eq(
+          profileBefore.filledOfferCount.addn(1)
+        )
+      );
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试报价生命周期事件
+  describe("offer events", async () => {
+    it("Emits events when Alice makes an offer and Bob fills it in two parts", async () => {
+      const offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+      const eventAccounts = { ...accounts, offer, vault };
+      const halfOfTokenBWanted = tokenBWantedAmount.divn(2);
+
+      const makeSignature = await program.methods
+        .makeOffer(
+          tokenAOfferedAmount,
+          tokenBWantedAmount,
+          null,
+          null,
+          null,
+          [],
+          [],
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({ ...eventAccounts })
+        .signers([alice])
+        .rpc();
+      await confirmTransaction(connection, makeSignature);
+
+      const [created] = await emittedEvents(makeSignature);
+      assert.equal(created.name, "offerCreated");
+      assert(created.data.offerId.eq(offerId));
+      assert(created.data.maker.equals(alice.publicKey));
+      assert(created.data.tokenAOfferedAmount.eq(tokenAOfferedAmount));
+
+      const partialSignature = await program.methods
+        .takeOffer(halfOfTokenBWanted, [], 0, new BN(0), U64_MAX, 0)
+        .accounts({ ...eventAccounts })
+        .signers([bob])
+        .rpc();
+      await confirmTransaction(connection, partialSignature);
+
+      const [partiallyTaken] = await emittedEvents(partialSignature);
+      assert.equal(partiallyTaken.name, "offerPartiallyTaken");
+      assert(partiallyTaken.data.taker.equals(bob.publicKey));
+      assert(partiallyTaken.data.tokenBAmount.eq(halfOfTokenBWanted));
+      assert(
+        partiallyTaken.data.tokenBRemainingAmount.eq(
+          tokenBWantedAmount.sub(halfOfTokenBWanted)
+        )
+      );
+
+      const takeSignature = await program.methods
+        .takeOffer(
+          tokenBWantedAmount.sub(halfOfTokenBWanted),
+          [],
+          0,
+          new BN(0),
+          U64_MAX,
+          0
+        )
+        .accounts({ ...eventAccounts })
+        .signers([bob])
+        .rpc();
+      await confirmTransaction(connection, takeSignature);
+
+      const [taken] = await emittedEvents(takeSignature);
+      assert.equal(taken.name, "offerTaken");
+      assert(taken.data.offerId.eq(offerId));
+      assert(taken.data.tokenMintA.equals(accounts.tokenMintA));
+      assert(taken.data.tokenMintB.equals(accounts.tokenMintB));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试恒定乘积流动性池
+  describe("liquidity pool", async () => {
+    const poolFeeBps = 30;
+    const depositAmount = new BN(1_000_000);
+    const swapAmount = new BN(10_000);
+
+    it("Lets Alice provide liquidity, swap against the pool and withdraw", async () => {
+      // 池子的代币A地址必须小于代币B地址
+      const mints: PublicKey[] = [];
+      for (let index = 0; index < 2; index++) {
+        const mint = await createMint(
+          connection,
+          payer,
+          payer.publicKey,
+          null,
+          6,
+          undefined,
+          {},
+          TOKEN_PROGRAM
+        );
+        const aliceAccount = await createAssociatedTokenAccountIdempotent(
+          connection,
+          payer,
+          mint,
+          alice.publicKey,
+          {},
+          TOKEN_PROGRAM
+        );
+        await mintTo(
+          connection,
+          payer,
+          mint,
+          aliceAccount,
+          payer,
+          1_000_000_000,
+          [],
+          {},
+          TOKEN_PROGRAM
+        );
+        mints.push(mint);
+      }
+      const [poolMintA, poolMintB] = mints.sort((a, b) =>
+        Buffer.compare(a.toBuffer(), b.toBuffer())
+      );
+
+      const pool = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool"), poolMintA.toBuffer(), poolMintB.toBuffer()],
+        program.programId
+      )[0];
+      const lpMint = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_lp"), pool.toBuffer()],
+        program.programId
+      )[0];
+      const aliceTokenAccountA = getAssociatedTokenAddressSync(
+        poolMintA,
+        alice.publicKey,
+        false,
+        TOKEN_PROGRAM
+      );
+      const aliceTokenAccountB = getAssociatedTokenAddressSync(
+        poolMintB,
+        alice.publicKey,
+        false,
+        TOKEN_PROGRAM
+      );
+      const aliceLpTokenAccount = getAssociatedTokenAddressSync(
+        lpMint,
+        alice.publicKey,
+        false,
+        TOKEN_PROGRAM
+      );
+      const poolAccounts = {
+        tokenMintA: poolMintA,
+        tokenMintB: poolMintB,
+        tokenProgramA: TOKEN_PROGRAM,
+        tokenProgramB: TOKEN_PROGRAM,
+      };
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .initPool(poolFeeBps)
+          .accounts({ ...poolAccounts, payer: payer.publicKey })
+          .rpc()
+      );
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .addLiquidity(depositAmount, depositAmount, new BN(0))
+          .accounts({ ...poolAccounts, provider: alice.publicKey })
+          .signers([alice])
+          .rpc()
+      );
+
+      // 首次注入的LP代币为几何平均数减去永久锁定的最低流动性
+      const lpBalance = new BN(
+        (await connection.getTokenAccountBalance(aliceLpTokenAccount)).value
+          .amount
+      );
+      assert(lpBalance.eq(depositAmount.subn(1_000)));
+
+      // 手续费从输入中扣除后按恒定乘积公式计算输出
+      const swapAmountAfterFee = swapAmount
+        .muln(10_000 - poolFeeBps)
+        .divn(10_000);
+      const expectedAmountOut = swapAmountAfterFee
+        .mul(depositAmount)
+        .div(depositAmount.add(swapAmountAfterFee));
+
+      try {
+        await program.methods
+          .swapExactIn(swapAmount, expectedAmountOut.addn(1), true)
+          .accounts({ ...poolAccounts, user: alice.publicKey })
+          .signers([alice])
+          .rpc();
+        assert.fail("Expected swapExactIn to throw an error");
+      } catch (error) {
+        assert.include(
+          error.toString(),
+          "Pool operation output is below the requested minimum"
+        );
+      }
+
+      const aliceTokenBBalanceBefore = new BN(
+        (await connection.getTokenAccountBalance(aliceTokenAccountB)).value
+          .amount
+      );
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .swapExactIn(swapAmount, expectedAmountOut, true)
+          .accounts({ ...poolAccounts, user: alice.publicKey })
+          .signers([alice])
+          .rpc()
+      );
+      const aliceTokenBBalanceAfter = new BN(
+        (await connection.getTokenAccountBalance(aliceTokenAccountB)).value
+          .amount
+      );
+      assert(
+        aliceTokenBBalanceAfter
+          .sub(aliceTokenBBalanceBefore)
+          .eq(expectedAmountOut)
+      );
+
+      const aliceTokenABalanceBefore = new BN(
+        (await connection.getTokenAccountBalance(aliceTokenAccountA)).value
+          .amount
+      );
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .removeLiquidity(lpBalance, new BN(0), new BN(0))
+          .accounts({ ...poolAccounts, provider: alice.publicKey })
+          .signers([alice])
+          .rpc()
+      );
+      const aliceTokenABalanceAfter = new BN(
+        (await connection.getTokenAccountBalance(aliceTokenAccountA)).value
+          .amount
+      );
+      assert(aliceTokenABalanceAfter.gt(aliceTokenABalanceBefore));
+      assert.equal(
+        (await connection.getTokenAccountBalance(aliceLpTokenAccount)).value
+          .amount,
+        "0"
+      );
+
+      // LP全部赎回后储备中仍有锁定的最低流动性，再次注入按储备比例进行，
+      // Alice不能以首次注入的价格独占剩余储备
+      const aliceTokenBBalanceBeforeReseed = new BN(
+        (await connection.getTokenAccountBalance(aliceTokenAccountB)).value
+          .amount
+      );
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .addLiquidity(depositAmount, depositAmount.muln(2), new BN(0))
+          .accounts({ ...poolAccounts, provider: alice.publicKey })
+          .signers([alice])
+          .rpc()
+      );
+      const aliceTokenBBalanceAfterReseed = new BN(
+        (await connection.getTokenAccountBalance(aliceTokenAccountB)).value
+          .amount
+      );
+      assert(
+        aliceTokenBBalanceBeforeReseed
+          .sub(aliceTokenBBalanceAfterReseed)
+          .lt(depositAmount)
+      );
+
+      // 代币被加入黑名单后不能再通过池子兑换
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .updateMintList({ denylist: {} }, [poolMintA])
+          .accounts({ admin: payer.publicKey })
+          .rpc()
+      );
+      try {
+        await program.methods
+          .swapExactIn(swapAmount, new BN(0), true)
+          .accounts({ ...poolAccounts, user: alice.publicKey })
+          .signers([alice])
+          .rpc();
+        assert.fail("Expected swapExactIn to throw an error");
+      } catch (error) {
+        assert.include(
+          error.toString(),
+          "Mint is not allowed by the mint list"
+        );
+      } finally {
+        await confirmTransaction(
+          connection,
+          await program.methods
+            .updateMintList({ disabled: {} }, [])
+            .accounts({ admin: payer.publicKey })
+            .rpc()
+        );
+      }
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  describe("config initialization", async () => {
+    it("Rejects initializing the config from a signer other than the upgrade authority", async () => {
+      try {
+        await program.methods
+          .initializeConfig(0, alice.publicKey, { tokenB: {} }, 0)
+          .accountsPartial({
+            admin: alice.publicKey,
+            programData: programDataAddress,
+          })
+          .signers([alice])
+          .rpc();
+        assert.fail("Expected initializeConfig to throw an error");
+      } catch (error) {
+        assert.include(error.toString(), "NotUpgradeAuthority");
+      }
+    });
+  });
+
+  // 测试带TransferHook扩展的Token-2022代币，钩子所需的额外账户通过remainingAccounts转发
+  describe("transfer hook mints", async () => {
+    const hookProgram = anchor.workspace.TransferHook as Program<TransferHook>;
+    const [hookMint] = makeKeypairs(1);
+    let offerId: BN;
+    let hookAccounts: Record<string, PublicKey>;
+
+    const extraAccountMetaList = PublicKey.findProgramAddressSync(
+      [Buffer.from("extra-account-metas"), hookMint.publicKey.toBuffer()],
+      hookProgram.programId
+    )[0];
+    const counter = PublicKey.findProgramAddressSync(
+      [Buffer.from("counter"), hookMint.publicKey.toBuffer()],
+      hookProgram.programId
+    )[0];
+    const hookRemainingAccounts = [
+      { pubkey: extraAccountMetaList, isSigner: false, isWritable: false },
+      { pubkey: counter, isSigner: false, isWritable: true },
+      { pubkey: hookProgram.programId, isSigner: false, isWritable: false },
+    ];
+
+    const hookTransfers = async () =>
+      (await hookProgram.account.counter.fetch(counter)).transfers;
+
+    const offerAccounts = (id: BN) => {
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          id.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+      const vault = getAssociatedTokenAddressSync(
+        hookMint.publicKey,
+        offer,
+        true,
+        TOKEN_2022_PROGRAM_ID
+      );
+      return { ...hookAccounts, offer, vault };
+    };
+
+    const makeHookOffer = async (id: BN) =>
+      confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            null,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts(offerAccounts(id))
+          .remainingAccounts(hookRemainingAccounts)
+          .signers([alice])
+          .rpc()
+      );
+
+    before(
+      "Creates a token A mint whose transfers run the hook program",
+      async () => {
+        const mintLen = getMintLen([ExtensionType.TransferHook]);
+        await sendAndConfirmTransaction(
+          connection,
+          new Transaction().add(
+            SystemProgram.createAccount({
+              fromPubkey: payer.publicKey,
+              newAccountPubkey: hookMint.publicKey,
+              space: mintLen,
+              lamports: await connection.getMinimumBalanceForRentExemption(
+                mintLen
+              ),
+              programId: TOKEN_2022_PROGRAM_ID,
+            }),
+            createInitializeTransferHookInstruction(
+              hookMint.publicKey,
+              payer.publicKey,
+              hookProgram.programId,
+              TOKEN_2022_PROGRAM_ID
+            ),
+            createInitializeMintInstruction(
+              hookMint.publicKey,
+              6,
+              payer.publicKey,
+              null,
+              TOKEN_2022_PROGRAM_ID
+            )
+          ),
+          [payer, hookMint]
+        );
+
+        await confirmTransaction(
+          connection,
+          await hookProgram.methods
+            .initializeExtraAccountMetaList()
+            .accounts({ payer: payer.publicKey, mint: hookMint.publicKey })
+            .rpc()
+        );
+
+        const aliceHookTokenAccount =
+          await createAssociatedTokenAccountIdempotent(
+            connection,
+            payer,
+            hookMint.publicKey,
+            alice.publicKey,
+            {},
+            TOKEN_2022_PROGRAM_ID
+          );
+        await mintTo(
+          connection,
+          payer,
+          hookMint.publicKey,
+          aliceHookTokenAccount,
+          payer,
+          1_000_000_000,
+          [],
+          {},
+          TOKEN_2022_PROGRAM_ID
+        );
+
+        hookAccounts = {
+          ...accounts,
+          tokenMintA: hookMint.publicKey,
+          tokenProgramA: TOKEN_2022_PROGRAM_ID,
+          makerTokenAccountA: aliceHookTokenAccount,
+          takerTokenAccountA: getAssociatedTokenAddressSync(
+            hookMint.publicKey,
+            bob.publicKey,
+            false,
+            TOKEN_2022_PROGRAM_ID
+          ),
+        };
+      }
+    );
+
+    it("Runs the hook on every transfer when Alice makes and Bob takes an offer", async () => {
+      const takenOfferId = await nextOfferId(accounts.maker);
+      const transfersBefore = await hookTransfers();
+
+      await makeHookOffer(takenOfferId);
+      const { vault } = offerAccounts(takenOfferId);
+      const vaultBalance = new BN(
+        (await connection.getTokenAccountBalance(vault)).value.amount
+      );
+      assert(vaultBalance.eq(tokenAOfferedAmount));
+      assert((await hookTransfers()).eq(transfersBefore.addn(1)));
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts(offerAccounts(takenOfferId))
+          .remainingAccounts(hookRemainingAccounts)
+          .signers([bob])
+          .rpc()
+      );
+
+      const bobHookBalance = new BN(
+        (
+          await connection.getTokenAccountBalance(
+            hookAccounts.takerTokenAccountA
+          )
+        ).value.amount
+      );
+      assert(bobHookBalance.eq(tokenAOfferedAmount));
+      assert((await hookTransfers()).eq(transfersBefore.addn(2)));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Rejects a take whose hook accounts are missing", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      await makeHookOffer(offerId);
+      const transfersBefore = await hookTransfers();
+
+      let failed = false;
+      try {
+        await program.methods
+          .takeOffer(tokenBWantedAmount, [], 0, new BN(0), U64_MAX, 0)
+          .accounts(offerAccounts(offerId))
+          .signers([bob])
+          .rpc();
+      } catch {
+        failed = true;
+      }
+      assert(failed, "Expected takeOffer to fail without the hook accounts");
+
+      // 托管的代币A原封不动，钩子也没有执行
+      const { vault } = offerAccounts(offerId);
+      const vaultBalance = new BN(
+        (await connection.getTokenAccountBalance(vault)).value.amount
+      );
+      assert(vaultBalance.eq(tokenAOfferedAmount));
+      assert((await hookTransfers()).eq(transfersBefore));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+
+    it("Returns the escrowed tokens through the hook when Alice cancels", async () => {
+      const aliceHookBalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(
+            hookAccounts.makerTokenAccountA
+          )
+        ).value.amount
+      );
+      const transfersBefore = await hookTransfers();
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .cancelOffer(offerId)
+          .accounts(offerAccounts(offerId))
+          .remainingAccounts(hookRemainingAccounts)
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceHookBalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(
+            hookAccounts.makerTokenAccountA
+          )
+        ).value.amount
+      );
+      assert(
+        aliceHookBalanceAfter.eq(
+          aliceHookBalanceBefore.add(tokenAOfferedAmount)
+        )
+      );
+      assert((await hookTransfers()).eq(transfersBefore.addn(1)));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
+  // 测试报价过期与回收功能
+  describe("offer expiry", async () => {
+    let offerId: BN;
+
+    it("Lets anyone reclaim an expired offer back to Alice", async () => {
+      offerId = await nextOfferId(accounts.maker);
+      const offer = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          accounts.maker.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+      const vault = getAssociatedTokenAddressSync(
+        accounts.tokenMintA,
+        offer,
+        true,
+        TOKEN_PROGRAM
+      );
+
+      const expiringAccounts = { ...accounts, offer, vault };
+      const expiresAt = new BN(Math.floor(Date.now() / SECONDS) + 2);
+
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .makeOffer(
+            tokenAOfferedAmount,
+            tokenBWantedAmount,
+            expiresAt,
+            null,
+            null,
+            [],
+            [],
+            null,
+            null,
+            null,
+            null
+          )
+          .accounts({ ...expiringAccounts })
+          .signers([alice])
+          .rpc()
+      );
+
+      const aliceTokenAccountABalanceBefore = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountA)
+        ).value.amount
+      );
+
+      // 等待报价过期
+      await new Promise((resolve) => setTimeout(resolve, 4 * SECONDS));
+
+      // Bob（非创建者）触发回收
+      await confirmTransaction(
+        connection,
+        await program.methods
+          .reclaimExpiredOffer()
+          .accounts({ ...expiringAccounts, payer: bob.publicKey })
+          .signers([bob])
+          .rpc()
+      );
+
+      const aliceTokenAccountABalanceAfter = new BN(
+        (
+          await connection.getTokenAccountBalance(accounts.makerTokenAccountA)
+        ).value.amount
+      );
+      assert(
+        aliceTokenAccountABalanceAfter
+          .sub(aliceTokenAccountABalanceBefore)
+          .eq(tokenAOfferedAmount)
+      );
+      assert.isNull(await connection.getAccountInfo(offer));
+      assert.isNull(await connection.getAccountInfo(vault));
+    }).slow(ANCHOR_SLOW_TEST_THRESHOLD);
+  });
+
   // 测试取消订单功能
   describe("cancel offer", async () => {
-    const offerId = getRandomBigNumber();
+    let offerId: BN;
 
     // 创建一个新的报价用于测试取消功能
     it("Creates a new offer for testing cancellation", async () => {
+      offerId = await nextOfferId(accounts.maker);
       // 确定新报价和资金库的账户地址
       const offer = PublicKey.findProgramAddressSync(
         [
@@ -217,7 +4542,19 @@ describe("swap", async () => {
       accounts.vault = vault;
 
       const transactionSignature = await program.methods
-        .makeOffer(offerId, tokenAOfferedAmount, tokenBWantedAmount)
+        .makeOffer(
+          tokenAOfferedAmount,
+          tokenBWantedAmount,
+          null,
+          null,
+          null,
+          [],
+          [],
+          null,
+          null,
+          null,
+          null
+        )
         .accounts({ ...accounts })
         .signers([alice])
         .rpc();
@@ -303,8 +4640,8 @@ describe("swap", async () => {
     });
 
     it("Prevents Bob from canceling Alice's offer", async () => {
-      // 创建一个新的报价ID
-      const newOfferId = getRandomBigNumber();
+      // 创建者档案分配的新报价ID
+      const newOfferId = await nextOfferId(accounts.maker);
 
       // 创建新报价
       const offer = PublicKey.findProgramAddressSync(
@@ -327,7 +4664,19 @@ describe("swap", async () => {
       const newAccounts = { ...accounts, offer, vault };
 
       const transactionSignature = await program.methods
-        .makeOffer(newOfferId, tokenAOfferedAmount, tokenBWantedAmount)
+        .makeOffer(
+          tokenAOfferedAmount,
+          tokenBWantedAmount,
+          null,
+          null,
+          null,
+          [],
+          [],
+          null,
+          null,
+          null,
+          null
+        )
         .accounts({ ...newAccounts })
         .signers([alice])
         .rpc();
@@ -362,7 +4711,8 @@ describe("swap", async () => {
           takerTokenAccountB: accounts.takerTokenAccountB,
           offer,
           vault,
-          tokenProgram: TOKEN_PROGRAM,
+          tokenProgramA: TOKEN_PROGRAM,
+          tokenProgramB: TOKEN_PROGRAM,
         };
 
         await program.methods